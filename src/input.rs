@@ -19,6 +19,8 @@ pub struct OverUI(pub bool);
 #[derive(Resource, Reflect, Clone, Copy, Default)]
 pub struct CursorPosition {
     pub position: Vec2,
+    /// Cursor position on the tabletop, as seen through the `TopdownCamera`
+    pub world_position: Vec2,
 }
 
 pub fn update_cursor_position(
@@ -55,14 +55,14 @@ impl SharableImage {
         })
     }
 
-    pub fn to_image(&self) -> Image {
+    pub fn to_image(&self, asset_usage: RenderAssetUsages) -> Image {
         let size = Extent3d {
             width: self.size.x,
             height: self.size.y,
             ..default()
         };
 
-        Image::new(size, TextureDimension::D2, self.data.clone(), self.format.to_bevy_format(), RenderAssetUsages::RENDER_WORLD)
+        Image::new(size, TextureDimension::D2, self.data.clone(), self.format.to_bevy_format(), asset_usage)
     }
 }
 
@@ -113,6 +113,30 @@ impl<T: Asset> SharedAssets<T> {
 
         (handle, uuid)
     }
+
+    /// Shares an asset that didn't come from the asset folder, like a map dropped onto the window
+    pub fn add_shared(&mut self, assets: &mut Assets<T>, asset: T) -> (Handle<T>, Uuid) {
+        let uuid = Uuid::new_v4();
+        (self.add_shared_with_id(assets, asset, uuid), uuid)
+    }
+
+    /// Shares an asset that is already loaded, like an image kept in the token library
+    pub fn share(&mut self, handle: Handle<T>) -> Uuid {
+        let uuid = Uuid::new_v4();
+        self.handle_to_id.insert(handle.clone_weak(), uuid);
+        self.id_to_handle.insert(uuid, handle);
+
+        uuid
+    }
+
+    /// Shares an asset under a known UUID, so entities of a loaded session keep pointing to it
+    pub fn add_shared_with_id(&mut self, assets: &mut Assets<T>, asset: T, uuid: Uuid) -> Handle<T> {
+        let handle = assets.add(asset);
+        self.id_to_handle.insert(uuid, handle.clone());
+        self.handle_to_id.insert(handle.clone_weak(), uuid);
+
+        handle
+    }
 }
 
 // Derive macro for some reason refuses to impl Default
@@ -163,7 +187,8 @@ impl Sharable for Image {
     }
     
     fn from_sharable(sharable: &Self::SharableType) -> Option<Self> {
-        Some(SharableImage::to_image(sharable))
+        // Clients never share images further, no need to keep them around outside of the GPU
+        Some(SharableImage::to_image(sharable, RenderAssetUsages::RENDER_WORLD))
     }
 }
 
@@ -172,11 +197,17 @@ fn send_requested_asset<T: Sharable>(
     shared_assets: Res<SharedAssets<T>>,
     mut requests: EventReader<server::MessageEvent<RequestAssetMessage<T>>>,
     mut connection: ResMut<server::ConnectionManager>,
+    roles: Res<PlayerRoles>,
 ) {
     for request in requests.read() {
         let client = request.context;
         let asset_id = request.message.id;
 
+        if !roles.client_role(client).allows(Permission::RequestAssets) {
+            info!("Client {} is not allowed to request assets", client.to_bits());
+            continue;
+        }
+
         let Some(asset) = shared_assets.id_to_handle.get(&asset_id) else {
             info!("Asset of type '{:?}' with UUID '{asset_id}' is not shared" , T::type_ident());
             continue;
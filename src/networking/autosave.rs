@@ -0,0 +1,333 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    networking::session::{load_session, save_session, SAVE_DIR},
+    prelude::*,
+};
+use bevy::app::AppExit;
+use lightyear::prelude::server::*;
+
+pub struct AutosavePlugin;
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autosave>()
+            .add_systems(Startup, check_last_shutdown)
+            .configure_sets(
+                OnEnter(NetworkingState::Started),
+                SpawnDefaults.run_if(no_session),
+            )
+            .add_systems(
+                OnEnter(NetworkingState::Started),
+                (
+                    create_lock,
+                    recover_session
+                        .run_if(resource_exists::<PendingRecovery>)
+                        .before(SpawnDefaults),
+                ),
+            )
+            .add_systems(OnExit(NetworkingState::Started), remove_lock)
+            // Restoring from the recovery window while already hosting replaces the table
+            .add_systems(
+                Update,
+                (
+                    recover_session.run_if(resource_exists::<PendingRecovery>),
+                    autosave,
+                )
+                    .chain()
+                    .run_if(in_state(NetworkingState::Started)),
+            )
+            .add_systems(Last, remove_lock_on_exit);
+    }
+}
+
+/// How often the hosted session is saved
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(120);
+
+/// Number of snapshots kept, the oldest one gets overwritten
+const AUTOSAVE_SLOTS: usize = 5;
+
+/// Exists while a session is hosted. Finding it on start, with the process that wrote it gone,
+/// means the last host didn't shut down cleanly
+const LOCK_FILE: &str = "hosting.lock";
+
+/// Systems spawning what a new session starts with. Skipped when a session was restored instead
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpawnDefaults;
+
+fn autosave_dir() -> PathBuf {
+    PathBuf::from(SAVE_DIR).join("autosave")
+}
+
+fn snapshot_path(slot: usize) -> PathBuf {
+    autosave_dir().join(format!("autosave-{slot}.session"))
+}
+
+fn lock_path() -> PathBuf {
+    autosave_dir().join(LOCK_FILE)
+}
+
+/// Slots with a snapshot on disk, with when it was written
+fn snapshot_times() -> Vec<(usize, SystemTime)> {
+    (0..AUTOSAVE_SLOTS)
+        .filter_map(|slot| {
+            let modified = std::fs::metadata(snapshot_path(slot))
+                .ok()?
+                .modified()
+                .ok()?;
+            Some((slot, modified))
+        })
+        .collect()
+}
+
+/// Slot of the newest snapshot
+fn latest_slot(snapshots: &[(usize, SystemTime)]) -> Option<usize> {
+    snapshots
+        .iter()
+        .max_by_key(|(_, modified)| *modified)
+        .map(|(slot, _)| *slot)
+}
+
+/// Slot of the oldest snapshot
+fn oldest_slot(snapshots: &[(usize, SystemTime)]) -> Option<usize> {
+    snapshots
+        .iter()
+        .min_by_key(|(_, modified)| *modified)
+        .map(|(slot, _)| *slot)
+}
+
+/// First empty slot, or the oldest snapshot once all of them are taken
+fn next_slot(snapshots: &[(usize, SystemTime)]) -> usize {
+    (0..AUTOSAVE_SLOTS)
+        .find(|slot| snapshots.iter().all(|(taken, _)| taken != slot))
+        .or_else(|| oldest_slot(snapshots))
+        .unwrap_or(0)
+}
+
+/// Newest snapshot on disk
+fn latest_snapshot() -> Option<PathBuf> {
+    latest_slot(&snapshot_times()).map(snapshot_path)
+}
+
+#[derive(Resource, Debug)]
+struct Autosave {
+    timer: Timer,
+    /// Slot the next snapshot goes to
+    next_slot: usize,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(AUTOSAVE_INTERVAL, TimerMode::Repeating),
+            next_slot: next_slot(&snapshot_times()),
+        }
+    }
+}
+
+/// Snapshot left behind by a host that didn't shut down cleanly, until it's restored or dismissed
+#[derive(Resource, Debug, Clone)]
+pub struct RecoveryOffer(pub PathBuf);
+
+/// Snapshot to restore as soon as the session is hosted
+#[derive(Resource, Debug, Clone)]
+pub struct PendingRecovery(pub PathBuf);
+
+/// Process id written to the lock, `None` when it's garbled
+fn parse_lock(contents: &str) -> Option<u32> {
+    contents.trim().parse().ok()
+}
+
+/// Whether the process that wrote the lock is still running, so it's hosting rather than crashed
+fn lock_owner_alive() -> Result<bool, String> {
+    let contents = std::fs::read_to_string(lock_path()).map_err(|err| err.to_string())?;
+    let Some(pid) = parse_lock(&contents) else {
+        return Ok(false);
+    };
+
+    if pid == std::process::id() {
+        return Ok(false);
+    }
+    process_alive(pid)
+}
+
+#[cfg(target_os = "linux")]
+fn process_alive(pid: u32) -> Result<bool, String> {
+    PathBuf::from("/proc")
+        .join(pid.to_string())
+        .try_exists()
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_alive(pid: u32) -> Result<bool, String> {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .map_err(|err| format!("failed to run kill: {err}"))
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> Result<bool, String> {
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .map_err(|err| format!("failed to run tasklist: {err}"))?;
+
+    if !output.status.success() {
+        return Err(format!("tasklist exited with {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn process_alive(_pid: u32) -> Result<bool, String> {
+    Err("no way to check for running processes on this platform".to_string())
+}
+
+fn check_last_shutdown(mut commands: Commands) {
+    let recover = std::env::args().any(|arg| arg == "--recover");
+    let crashed = lock_path().exists()
+        && match lock_owner_alive() {
+            Ok(alive) => !alive,
+            // Could still be hosting, so no offer, but --recover works all the same
+            Err(err) => {
+                error!("Failed to check whether the last host is still running: {err}");
+                false
+            }
+        };
+
+    if !recover && !crashed {
+        return;
+    }
+
+    let Some(snapshot) = latest_snapshot() else {
+        if recover {
+            warn!(
+                "Nothing to recover, no autosaves found in {:?}",
+                autosave_dir()
+            );
+        }
+        return;
+    };
+
+    if recover {
+        info!("Session will be restored from {snapshot:?} once hosting starts");
+        commands.insert_resource(PendingRecovery(snapshot));
+    } else {
+        warn!(
+            "Last hosted session didn't shut down cleanly. Start with --recover to restore {snapshot:?}"
+        );
+        commands.insert_resource(RecoveryOffer(snapshot));
+    }
+}
+
+fn create_lock() {
+    let result = std::fs::create_dir_all(autosave_dir())
+        .and_then(|_| std::fs::write(lock_path(), std::process::id().to_string()));
+
+    if let Err(err) = result {
+        error!("Failed to create {:?}: {err}", lock_path());
+    }
+}
+
+/// Also used when the host dismisses a recovery offer, so it isn't made again
+pub fn remove_lock() {
+    _ = std::fs::remove_file(lock_path());
+}
+
+fn remove_lock_on_exit(mut exit: EventReader<AppExit>, state: Res<State<NetworkingState>>) {
+    if exit.read().next().is_some() && matches!(state.get(), NetworkingState::Started) {
+        remove_lock();
+    }
+}
+
+/// Nothing left to spawn defaults for once a session is on the table
+fn no_session(scenes: Query<(), With<TableScene>>) -> bool {
+    scenes.is_empty()
+}
+
+/// Runs before the defaults of a new session are spawned, so they're only spawned when it fails
+fn recover_session(world: &mut World) {
+    let Some(PendingRecovery(path)) = world.remove_resource::<PendingRecovery>() else {
+        return;
+    };
+    world.remove_resource::<RecoveryOffer>();
+
+    match load_session(world, &path) {
+        Ok(()) => info!("Session recovered from {path:?}"),
+        Err(err) => error!("Failed to recover session from {path:?}: {err}"),
+    }
+}
+
+fn autosave(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+    let mut autosave = world.resource_mut::<Autosave>();
+    if !autosave.timer.tick(delta).just_finished() {
+        return;
+    }
+
+    let slot = autosave.next_slot;
+    autosave.next_slot = (slot + 1) % AUTOSAVE_SLOTS;
+
+    let path = snapshot_path(slot);
+    match save_session(world, &path) {
+        Ok(()) => info!("Autosaved to {path:?}"),
+        Err(err) => error!("Autosave failed: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written_at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn latest_and_oldest_go_by_write_time() {
+        let snapshots = [
+            (0, written_at(30)),
+            (1, written_at(50)),
+            (2, written_at(10)),
+        ];
+        assert_eq!(latest_slot(&snapshots), Some(1));
+        assert_eq!(oldest_slot(&snapshots), Some(2));
+        assert_eq!(latest_slot(&[]), None);
+        assert_eq!(oldest_slot(&[]), None);
+    }
+
+    #[test]
+    fn next_slot_fills_empty_slots_first() {
+        assert_eq!(next_slot(&[]), 0);
+        assert_eq!(next_slot(&[(0, written_at(10)), (1, written_at(20))]), 2);
+        assert_eq!(next_slot(&[(1, written_at(10))]), 0);
+    }
+
+    #[test]
+    fn next_slot_overwrites_oldest_once_full() {
+        let snapshots = (0..AUTOSAVE_SLOTS)
+            .map(|slot| {
+                (
+                    slot,
+                    written_at(if slot == 3 { 5 } else { 100 + slot as u64 }),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(next_slot(&snapshots), 3);
+    }
+
+    #[test]
+    fn lock_holds_a_process_id() {
+        assert_eq!(parse_lock("1234"), Some(1234));
+        assert_eq!(parse_lock(" 1234\n"), Some(1234));
+        assert_eq!(parse_lock(""), None);
+        assert_eq!(parse_lock("host"), None);
+        assert_eq!(parse_lock("-1"), None);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    networking::{
+        history::{ApplyEdit, Edit, RecordEdit},
+        scenes::ClientScenes,
+        tokens::can_control,
+        vision::VisibleTo,
+    },
+    prelude::*,
+};
+use lightyear::prelude::{server::*, *};
+
+pub struct BarPlugin;
+impl Plugin for BarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (handle_bar_requests, update_bar_audience)
+                .chain()
+                .run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// Limit of bars per token
+const MAX_BARS: usize = 8;
+
+/// Longest label of a bar
+const MAX_BAR_LABEL: usize = 16;
+
+/// Clients a bar is replicated to. Nobody gets a new bar until its audience is worked out
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BarAudience(Vec<u64>);
+
+fn sanitize(bar: &mut ResourceBar) {
+    bar.label = bar.label.chars().take(MAX_BAR_LABEL).collect();
+    bar.max = bar.max.max(0);
+}
+
+fn handle_bar_requests(
+    mut commands: Commands,
+    mut requests: EventReader<MessageEvent<BarMessage>>,
+    bars: Query<(Entity, &ResourceBar)>,
+    tokens: Query<Option<&TokenOwners>, With<Token>>,
+    mut recorded: EventWriter<RecordEdit>,
+    mut edits: EventWriter<ApplyEdit>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+        let is_gm = roles.is_gm(client_id);
+
+        let current = match &request.message {
+            BarMessage::Add(_) => None,
+            BarMessage::Update(entity, _)
+            | BarMessage::Adjust(entity, _)
+            | BarMessage::Remove(entity) => bars.get(*entity).ok().map(|(_, bar)| bar),
+        };
+
+        // Bars are edited by whoever controls their token
+        let token = match &request.message {
+            BarMessage::Add(bar) => Some(bar.token),
+            _ => current.map(|bar| bar.token),
+        };
+
+        let allowed = token
+            .and_then(|token| tokens.get(token).ok())
+            .is_some_and(|owners| can_control(&roles, client_id, Permission::EditTokens, owners));
+
+        // Players could hide a bar from themselves, but only the GM decides what's secret,
+        // and GM only bars stay untouched by players
+        let hides_from_players = match &request.message {
+            BarMessage::Add(bar) | BarMessage::Update(_, bar) => {
+                bar.visibility == BarVisibility::Gm
+            }
+            _ => false,
+        };
+        let is_secret = current.is_some_and(|bar| bar.visibility == BarVisibility::Gm);
+
+        if !allowed || ((hides_from_players || is_secret) && !is_gm) {
+            info!(
+                "Rejected {:?} from client {}",
+                request.message,
+                client_id.to_bits()
+            );
+            continue;
+        }
+
+        // Changes go through the history, so they can be undone like other token edits
+        let edit = match &request.message {
+            BarMessage::Add(bar) => {
+                let count = bars
+                    .iter()
+                    .filter(|(_, other)| other.token == bar.token)
+                    .count();
+                if count >= MAX_BARS {
+                    continue;
+                }
+
+                let mut bar = bar.clone();
+                sanitize(&mut bar);
+
+                let entity = commands
+                    .spawn((
+                        Name::new(format!("{} bar", bar.label)),
+                        bar,
+                        BarAudience::default(),
+                        server::Replicate {
+                            target: ReplicationTarget {
+                                target: NetworkTarget::None,
+                            },
+                            ..default()
+                        },
+                    ))
+                    .id();
+
+                recorded.send(RecordEdit {
+                    client: client_id,
+                    permission: Permission::EditTokens,
+                    undo: Edit::Despawn(vec![entity]),
+                });
+                continue;
+            }
+            // Bars can't be moved to another token, and the value only changes by adjusting it
+            BarMessage::Update(entity, new_bar) => {
+                let mut update = new_bar.clone();
+                sanitize(&mut update);
+                Edit::UpdateBar {
+                    bar: *entity,
+                    update,
+                }
+            }
+            BarMessage::Adjust(entity, delta) => Edit::AdjustBar {
+                bar: *entity,
+                delta: *delta,
+            },
+            BarMessage::Remove(entity) => Edit::Despawn(vec![*entity]),
+        };
+
+        edits.send(ApplyEdit {
+            client: client_id,
+            permission: Permission::EditTokens,
+            edit,
+        });
+    }
+}
+
+/// Replicates bars only to clients allowed to see them, and removes bars of deleted tokens
+fn update_bar_audience(
+    mut commands: Commands,
+    mut bars: Query<(
+        Entity,
+        &ResourceBar,
+        &mut BarAudience,
+        &mut ReplicationTarget,
+    )>,
+    tokens: Query<(Option<&TokenOwners>, Option<&VisibleTo>, Option<&InScene>), With<Token>>,
+    client_scenes: Res<ClientScenes>,
+    roles: Res<PlayerRoles>,
+) {
+    for (entity, bar, mut audience, mut target) in bars.iter_mut() {
+        let Ok((owners, visible_to, scene)) = tokens.get(bar.token) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        // Bars never go to clients that can't see their token
+        let new_audience = match bar.visibility {
+            // Tokens nobody has been shown yet keep their bars hidden too
+            BarVisibility::Everyone => visible_to
+                .map(|visible_to| visible_to.0.clone())
+                .unwrap_or_default(),
+            visibility => {
+                let mut clients = client_scenes
+                    .iter()
+                    .filter(|(_, client_scene)| Some(**client_scene) == scene.map(|scene| scene.0))
+                    .map(|(client, _)| *client)
+                    .filter(|client| {
+                        let is_gm = roles.client_role(ClientId::from_bits(*client)) == Role::Gm;
+                        let is_owner = owners.is_some_and(|owners| owners.contains(client));
+                        is_gm || (visibility == BarVisibility::Owner && is_owner)
+                    })
+                    .collect::<Vec<_>>();
+                clients.sort_unstable();
+                clients
+            }
+        };
+
+        if audience.0 == new_audience {
+            continue;
+        }
+
+        target.target = NetworkTarget::Only(
+            new_audience
+                .iter()
+                .map(|client| ClientId::from_bits(*client))
+                .collect(),
+        );
+        audience.0 = new_audience;
+    }
+}
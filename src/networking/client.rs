@@ -1,6 +1,6 @@
 use std::net::{Ipv4Addr, SocketAddrV4};
 
-use crate::{input::CursorPosition, prelude::*, tabletop::TopdownCamera};
+use crate::{input::CursorPosition, prelude::*};
 use client::*;
 use lightyear::{connection::netcode::PRIVATE_KEY_BYTES, prelude::*};
 use rand::RngCore;
@@ -55,6 +55,7 @@ impl Plugin for ClientPlugin {
                     update_local_cursor_position,
                     init_replicated_cursors,
                     init_replicated_tokens,
+                    init_replicated_maps,
                     update_replicated_cursor_color,
                 ).run_if(in_state(NetworkingState::Connected)),
             );
@@ -73,6 +74,27 @@ impl Plugin for ClientPlugin {
 #[derive(Resource, Clone, Copy, Deref, DerefMut)]
 pub struct ClientId(pub u64);
 
+/// Role of the local player. Host is always the GM
+pub fn local_role(
+    client_id: &ClientId,
+    roles: &PlayerRoles,
+    server_state: &State<server::NetworkingState>,
+) -> Role {
+    match server_state.get() {
+        server::NetworkingState::Started => Role::Gm,
+        server::NetworkingState::Stopped => roles.role(client_id.0),
+    }
+}
+
+/// Run condition for GM only tools and windows
+pub fn local_player_is_gm(
+    client_id: Res<ClientId>,
+    roles: Res<PlayerRoles>,
+    server_state: Res<State<server::NetworkingState>>,
+) -> bool {
+    local_role(&client_id, &roles, &server_state) == Role::Gm
+}
+
 fn send_player_info(mut connection: ResMut<ConnectionManager>, player: Res<Player>) {
     _ = connection.send_message::<UnorderedReliable, Player>(&player);
 }
@@ -83,6 +105,7 @@ fn recieve_message(mut messages: EventReader<MessageEvent<ChatMessage>>) {
             ChatMessage::Message(client, message) => info!("{client}: {message}"),
             ChatMessage::Connected(client) => info!("Client {client} connected"),
             ChatMessage::Disconnected(client) => info!("Client {client} disconnected"),
+            ChatMessage::Turn { round, name } => info!("Round {round}: {name}'s turn"),
         }
     }
 }
@@ -104,7 +127,6 @@ fn update_local_cursor_position(
             Without<Interpolated>,
         ),
     >,
-    camera: Query<(&Camera, &GlobalTransform), With<TopdownCamera>>,
     cursor_pos: Res<CursorPosition>,
     mouse: Res<ButtonInput<MouseButton>>,
 ) {
@@ -116,13 +138,8 @@ fn update_local_cursor_position(
         return;
     }
 
-    let (camera, camera_transform) = camera.single();
     let mut local_cursor = local_cursor.single_mut();
-
-    if let Some(position) = camera.viewport_to_world_2d(camera_transform, cursor_pos.position) {
-        let desired_pos = position + Vec2::new(0.25, -0.25);
-        local_cursor.position = desired_pos;
-    }
+    local_cursor.position = cursor_pos.world_position + Vec2::new(0.25, -0.25);
 }
 
 
@@ -163,6 +180,38 @@ fn init_replicated_tokens(
     }
 }
 
+fn init_replicated_maps(
+    mut commands: Commands,
+    maps: Query<(Entity, &MapBackground, &SharedAsset<Image>), Or<(Added<Replicated>, Added<Interpolated>)>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    image_assets: Res<Assets<Image>>,
+    mut connection: ResMut<ConnectionManager>,
+) {
+    for (entity, map, shared_image) in maps.iter() {
+        if image_assets.get(shared_image.id).is_none() {
+            _ = connection.send_message::<UnorderedReliable, _>(&RequestAssetMessage::<Image>::new(shared_image.id));
+        }
+
+        let image = Handle::<Image>::weak_from_u128(shared_image.as_u128());
+        let map_material = materials.add(StandardMaterial {
+            unlit: true,
+            base_color_texture: Some(image),
+            ..default()
+        });
+
+        commands.entity(entity).insert((
+            Name::new("Map background"),
+            PbrBundle {
+                transform: map.transform(),
+                mesh: meshes.add(Mesh::from(Rectangle::new(1.0, 1.0))),
+                material: map_material,
+                ..default()
+            },
+        ));
+    }
+}
+
 fn init_replicated_cursors(
     mut commands: Commands,
     cursors: Query<(Entity, &Owner, &Cursor), Or<(Added<Replicated>, Added<Interpolated>)>>,
@@ -0,0 +1,47 @@
+use crate::prelude::*;
+use lightyear::prelude::server::*;
+
+pub struct ConditionPlugin;
+impl Plugin for ConditionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConditionCatalogue>().add_systems(
+            Update,
+            handle_catalogue_requests.run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// Longest label or icon allowed in the catalogue and on tokens
+const MAX_CONDITION_TEXT: usize = 32;
+
+pub fn truncate_condition(mut condition: Condition) -> Condition {
+    condition.label = condition.label.chars().take(MAX_CONDITION_TEXT).collect();
+    condition.icon = condition.icon.chars().take(MAX_CONDITION_TEXT).collect();
+    condition
+}
+
+fn handle_catalogue_requests(
+    mut requests: EventReader<MessageEvent<SetConditionCatalogueMessage>>,
+    mut catalogue: ResMut<ConditionCatalogue>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        if !roles
+            .client_role(request.context)
+            .allows(Permission::ManageTable)
+        {
+            info!(
+                "Client {} is not allowed to change conditions",
+                request.context.to_bits()
+            );
+            continue;
+        }
+
+        let new_catalogue = &request.message.0;
+        catalogue.0 = new_catalogue
+            .iter()
+            .cloned()
+            .map(truncate_condition)
+            .collect();
+    }
+}
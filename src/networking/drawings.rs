@@ -0,0 +1,132 @@
+use crate::{
+    networking::{
+        history::{ApplyEdit, Edit, RecordEdit},
+        scenes::client_scene,
+    },
+    prelude::*,
+};
+use lightyear::prelude::{server::*, *};
+
+pub struct DrawingPlugin;
+impl Plugin for DrawingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            handle_draw_requests.run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// Longer strokes are cut off
+const MAX_STROKE_POINTS: usize = 2048;
+
+/// Widest line a pen or highlighter can draw
+pub const MAX_STROKE_WIDTH: f32 = 2.0;
+
+fn handle_draw_requests(
+    mut commands: Commands,
+    mut requests: EventReader<MessageEvent<DrawMessage>>,
+    strokes: Query<(Entity, &Owner, Option<&InScene>), With<Stroke>>,
+    scenes: Query<(Entity, &TableScene)>,
+    mut recorded: EventWriter<RecordEdit>,
+    mut deletions: EventWriter<ApplyEdit>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+        let role = roles.client_role(client_id);
+
+        if !role.allows(Permission::Annotate) {
+            info!("Client {} is not allowed to draw", client_id.to_bits());
+            continue;
+        }
+
+        // Strokes of other scenes can't be seen, so they're left alone
+        let scene = client_scene(scenes.iter(), client_id.to_bits());
+        let on_scene = |in_scene: Option<&InScene>| in_scene.map(|in_scene| in_scene.0) == scene;
+
+        match &request.message {
+            DrawMessage::Stroke(stroke) => {
+                let valid = !stroke.points.is_empty()
+                    && stroke.points.iter().all(|point| point.is_finite())
+                    && stroke.width > 0.0
+                    && stroke.width <= MAX_STROKE_WIDTH;
+
+                if !valid {
+                    continue;
+                }
+
+                let mut stroke = stroke.clone();
+                stroke.points.truncate(MAX_STROKE_POINTS);
+
+                let entity = commands
+                    .spawn((
+                        Name::new("Stroke"),
+                        stroke,
+                        Owner(client_id.to_bits()),
+                        server::Replicate {
+                            target: ReplicationTarget {
+                                target: NetworkTarget::None,
+                            },
+                            ..default()
+                        },
+                    ))
+                    .id();
+
+                recorded.send(RecordEdit {
+                    client: client_id,
+                    permission: Permission::Annotate,
+                    undo: Edit::Despawn(vec![entity]),
+                });
+            }
+            // Removed strokes go through the history, so they can be brought back
+            DrawMessage::Remove(entities) => {
+                let removed = strokes
+                    .iter_many(entities)
+                    .filter(|(_, owner, in_scene)| {
+                        (owner.0 == client_id.to_bits() || role == Role::Gm) && on_scene(*in_scene)
+                    })
+                    .map(|(entity, _, _)| entity)
+                    .collect::<Vec<_>>();
+
+                deletions.send(ApplyEdit {
+                    client: client_id,
+                    permission: Permission::Annotate,
+                    edit: Edit::Despawn(removed),
+                });
+            }
+            DrawMessage::ClearMine => {
+                let removed = strokes
+                    .iter()
+                    .filter(|(_, owner, in_scene)| {
+                        owner.0 == client_id.to_bits() && on_scene(*in_scene)
+                    })
+                    .map(|(entity, _, _)| entity)
+                    .collect::<Vec<_>>();
+
+                deletions.send(ApplyEdit {
+                    client: client_id,
+                    permission: Permission::Annotate,
+                    edit: Edit::Despawn(removed),
+                });
+            }
+            DrawMessage::ClearAll => {
+                if role != Role::Gm {
+                    continue;
+                }
+
+                deletions.send(ApplyEdit {
+                    client: client_id,
+                    permission: Permission::ManageTable,
+                    edit: Edit::Despawn(
+                        strokes
+                            .iter()
+                            .filter(|(_, _, in_scene)| on_scene(*in_scene))
+                            .map(|(entity, _, _)| entity)
+                            .collect(),
+                    ),
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    networking::{autosave::SpawnDefaults, scenes::client_scene},
+    prelude::*,
+};
+use lightyear::prelude::{server::*, *};
+
+pub struct FogPlugin;
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(NetworkingState::Started),
+            spawn_fog.run_if(run_once()).in_set(SpawnDefaults),
+        )
+        .add_systems(
+            Update,
+            handle_fog_requests.run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// Ops kept before they're collapsed into the base mask
+const MAX_FOG_OPS: usize = 64;
+
+/// Fog of the first scene, the scene is assigned once it exists
+fn spawn_fog(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Fog of war"),
+        FogOfWar::default(),
+        server::Replicate {
+            target: ReplicationTarget {
+                target: NetworkTarget::None,
+            },
+            ..default()
+        },
+    ));
+}
+
+fn is_valid(op: &FogOp) -> bool {
+    let (FogOp::Reveal(shape) | FogOp::Hide(shape)) = op;
+
+    match shape {
+        FogShape::Brush { points, radius } => {
+            !points.is_empty()
+                && points.len() <= MAX_FOG_SHAPE_POINTS
+                && points.iter().all(|point| point.is_finite())
+                && radius.is_finite()
+                && *radius > 0.0
+        }
+        FogShape::Polygon(points) => {
+            points.len() >= 3
+                && points.len() <= MAX_FOG_SHAPE_POINTS
+                && points.iter().all(|point| point.is_finite())
+        }
+    }
+}
+
+fn handle_fog_requests(
+    mut requests: EventReader<MessageEvent<FogMessage>>,
+    mut fogs: Query<(&mut FogOfWar, Option<&InScene>)>,
+    scenes: Query<(Entity, &TableScene)>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        if !roles
+            .client_role(request.context)
+            .allows(Permission::ManageTable)
+        {
+            info!(
+                "Client {} is not allowed to change fog of war",
+                request.context.to_bits()
+            );
+            continue;
+        }
+
+        // Each scene has its own fog, only the one the GM is looking at changes
+        let scene = client_scene(scenes.iter(), request.context.to_bits());
+        for (mut fog, _) in fogs
+            .iter_mut()
+            .filter(|(_, in_scene)| in_scene.map(|in_scene| in_scene.0) == scene)
+        {
+            match &request.message {
+                FogMessage::Apply(op) => {
+                    if is_valid(op) {
+                        fog.ops.push(op.clone());
+                        if fog.ops.len() > MAX_FOG_OPS {
+                            fog.compact();
+                        }
+                    }
+                }
+                FogMessage::SetEnabled(enabled) => fog.enabled = *enabled,
+                FogMessage::SetVision(vision) => fog.vision = *vision,
+                FogMessage::HideAll => {
+                    fog.base = FogMask::default();
+                    fog.ops.clear();
+                }
+                FogMessage::RevealAll => {
+                    let (min, max) = (fog.min, fog.max);
+                    fog.base = FogMask::default();
+                    fog.ops = vec![FogOp::Reveal(FogShape::Polygon(vec![
+                        min,
+                        Vec2::new(max.x, min.y),
+                        max,
+                        Vec2::new(min.x, max.y),
+                    ]))];
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{
+    networking::{autosave::SpawnDefaults, scenes::client_scene},
+    prelude::*,
+};
+use lightyear::prelude::{server::*, *};
+
+pub struct GridPlugin;
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(NetworkingState::Started),
+            spawn_grid.run_if(run_once()).in_set(SpawnDefaults),
+        )
+        .add_systems(
+            Update,
+            handle_grid_requests.run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+fn spawn_grid(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Grid settings"),
+        GridSettings::default(),
+        server::Replicate {
+            target: ReplicationTarget {
+                target: NetworkTarget::None,
+            },
+            ..default()
+        },
+    ));
+}
+
+fn handle_grid_requests(
+    mut requests: EventReader<MessageEvent<SetGridMessage>>,
+    mut grids: Query<(&mut GridSettings, Option<&InScene>)>,
+    scenes: Query<(Entity, &TableScene)>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        if !roles
+            .client_role(request.context)
+            .allows(Permission::ManageTable)
+        {
+            info!(
+                "Client {} is not allowed to change the grid",
+                request.context.to_bits()
+            );
+            continue;
+        }
+
+        let mut settings = request.message.0.clone();
+        if !settings.cell_size.is_finite()
+            || !settings.origin.is_finite()
+            || !settings.cell_distance.is_finite()
+        {
+            continue;
+        }
+        settings.cell_size = settings.cell_size.max(0.1);
+        settings.opacity = settings.opacity.clamp(0.0, 1.0);
+        settings.distance_unit = settings.distance_unit.chars().take(8).collect();
+
+        // Each scene has its own grid, only the one the GM is looking at changes
+        let scene = client_scene(scenes.iter(), request.context.to_bits());
+        for (mut grid, _) in grids
+            .iter_mut()
+            .filter(|(_, in_scene)| in_scene.map(|in_scene| in_scene.0) == scene)
+        {
+            *grid = settings.clone();
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Per player undo and redo of table edits.
+//!
+//! Token moves, token edits, bar edits and spawning or deleting tokens, bars and strokes
+//! can be undone. Walls, doors, fog, templates, maps, grid settings, scenes and the combat
+//! tracker intentionally can't: they're set up by the GM with their own tools, which already
+//! remove or reset what they placed, and undoing them could reveal or hide parts of the table
+//! behind everyone's back.
+
+use std::collections::VecDeque;
+
+use crate::{
+    networking::{
+        session::{save_entities, spawn_saved_entities, SavedEntity},
+        tokens::{can_control, DraggedTokens},
+    },
+    prelude::*,
+};
+use lightyear::prelude::{server::*, *};
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_event::<RecordEdit>()
+            .add_event::<ApplyEdit>()
+            .add_systems(
+                Update,
+                (record_edits, apply_edits, handle_history_requests)
+                    .chain()
+                    .run_if(in_state(NetworkingState::Started)),
+            );
+    }
+}
+
+/// Edits each player can undo, older ones are forgotten
+const MAX_HISTORY: usize = 100;
+
+/// Reversible change to the table. Applying one gives back the edit that reverts it
+#[derive(Debug, Clone)]
+pub enum Edit {
+    MoveToken {
+        token: Entity,
+        position: Vec2,
+    },
+    EditToken {
+        token: Entity,
+        edit: TokenEdit,
+    },
+    /// Everything of the bar but its value, like [`BarMessage::Update`]
+    UpdateBar {
+        bar: Entity,
+        update: ResourceBar,
+    },
+    AdjustBar {
+        bar: Entity,
+        delta: i32,
+    },
+    Despawn(Vec<Entity>),
+    Respawn(Vec<SavedEntity>),
+}
+
+/// Edit along with what it takes to make it
+#[derive(Debug, Clone)]
+struct Entry {
+    edit: Edit,
+    permission: Permission,
+}
+
+/// Edit a client has just made, recorded as the edit that undoes it
+#[derive(Event, Debug, Clone)]
+pub struct RecordEdit {
+    pub client: ClientId,
+    pub permission: Permission,
+    pub undo: Edit,
+}
+
+/// Edit made through the history, so it can be undone. Deletions go this way,
+/// since a despawned entity can't be saved anymore
+#[derive(Event, Debug, Clone)]
+pub struct ApplyEdit {
+    pub client: ClientId,
+    pub permission: Permission,
+    pub edit: Edit,
+}
+
+#[derive(Default, Debug)]
+struct UserHistory {
+    undo: VecDeque<Entry>,
+    redo: Vec<Entry>,
+}
+
+impl UserHistory {
+    fn push_undo(&mut self, entry: Entry) {
+        self.undo.push_back(entry);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.pop_front();
+        }
+    }
+}
+
+/// Undo and redo stacks of every player
+#[derive(Resource, Default, Debug)]
+pub struct History {
+    users: HashMap<ClientId, UserHistory>,
+    /// Deleted entities that were brought back, to the entities they became
+    respawned: HashMap<Entity, Entity>,
+}
+
+/// Follows an entity through every time it was deleted and brought back
+fn resolve(respawned: &HashMap<Entity, Entity>, mut entity: Entity) -> Entity {
+    while let Some(next) = respawned.get(&entity) {
+        entity = *next;
+    }
+    entity
+}
+
+fn record_edits(mut edits: EventReader<RecordEdit>, mut history: ResMut<History>) {
+    for edit in edits.read() {
+        let user = history.users.entry(edit.client).or_default();
+        user.push_undo(Entry {
+            edit: edit.undo.clone(),
+            permission: edit.permission,
+        });
+        user.redo.clear();
+    }
+}
+
+fn apply_edits(world: &mut World) {
+    let edits = world
+        .resource_mut::<Events<ApplyEdit>>()
+        .drain()
+        .collect::<Vec<_>>();
+
+    for edit in edits {
+        let entry = Entry {
+            edit: edit.edit,
+            permission: edit.permission,
+        };
+
+        let Some(undo) = apply_entry(world, edit.client, entry) else {
+            continue;
+        };
+
+        let mut history = world.resource_mut::<History>();
+        let user = history.users.entry(edit.client).or_default();
+        user.push_undo(undo);
+        user.redo.clear();
+    }
+}
+
+fn handle_history_requests(world: &mut World) {
+    let requests = world
+        .resource_mut::<Events<MessageEvent<HistoryMessage>>>()
+        .drain()
+        .map(|request| (request.context, request.message))
+        .collect::<Vec<_>>();
+
+    for (client_id, message) in requests {
+        // Edits that can't be made anymore are dropped, and the one before them is tried
+        loop {
+            let mut history = world.resource_mut::<History>();
+            let user = history.users.entry(client_id).or_default();
+            let entry = match message {
+                HistoryMessage::Undo => user.undo.pop_back(),
+                HistoryMessage::Redo => user.redo.pop(),
+            };
+
+            let Some(entry) = entry else {
+                break;
+            };
+
+            let Some(reverse) = apply_entry(world, client_id, entry) else {
+                continue;
+            };
+
+            let mut history = world.resource_mut::<History>();
+            let user = history.users.entry(client_id).or_default();
+            match message {
+                HistoryMessage::Undo => user.redo.push(reverse),
+                HistoryMessage::Redo => user.push_undo(reverse),
+            }
+            break;
+        }
+    }
+}
+
+/// Makes the edit if the client is still allowed to, and returns the edit that reverts it
+fn apply_entry(world: &mut World, client_id: ClientId, entry: Entry) -> Option<Entry> {
+    let roles = world.resource::<PlayerRoles>().clone();
+    let respawned = world.resource::<History>().respawned.clone();
+    let permission = entry.permission;
+
+    let can_control_token = |world: &World, token: Entity| {
+        world.get::<Token>(token).is_some()
+            && can_control(
+                &roles,
+                client_id,
+                permission,
+                world.get::<TokenOwners>(token),
+            )
+    };
+
+    let edit = match entry.edit {
+        Edit::MoveToken { token, position } => {
+            let token = resolve(&respawned, token);
+
+            // Whoever is dragging it right now wins
+            if !can_control_token(world, token)
+                || world.resource::<DraggedTokens>().contains_key(&token)
+            {
+                return None;
+            }
+
+            let mut moved = world.get_mut::<Token>(token)?;
+            let old_position = moved.position;
+            moved.position = position;
+
+            Edit::MoveToken {
+                token,
+                position: old_position,
+            }
+        }
+        Edit::EditToken { token, edit } => {
+            let token = resolve(&respawned, token);
+            if !can_control_token(world, token) {
+                return None;
+            }
+
+            let mut entity = world.entity_mut(token);
+            let old_edit = match edit {
+                TokenEdit::SetOwners(owners) => {
+                    let old = entity.get::<TokenOwners>().cloned().unwrap_or_default();
+                    entity.insert(TokenOwners(owners));
+                    TokenEdit::SetOwners(old.0)
+                }
+                TokenEdit::SetSight(radius) => {
+                    let old = entity.get::<Sight>().copied().unwrap_or_default();
+                    entity.insert(Sight { radius });
+                    TokenEdit::SetSight(old.radius)
+                }
+                TokenEdit::SetConditions(conditions) => {
+                    let old = entity.get::<TokenConditions>().cloned().unwrap_or_default();
+                    entity.insert(TokenConditions(conditions));
+                    TokenEdit::SetConditions(old.0)
+                }
+                TokenEdit::Rename(name) => {
+                    let old = entity.get::<TokenName>().cloned().unwrap_or_default();
+                    entity.insert(TokenName(name));
+                    TokenEdit::Rename(old.0)
+                }
+                TokenEdit::SetImage(image) => {
+                    let old = entity.get::<SharedAsset<Image>>()?.id;
+                    entity.insert(SharedAsset::<Image>::new(image));
+                    TokenEdit::SetImage(old)
+                }
+                TokenEdit::SetSize(size) => {
+                    let old = entity.get::<TokenSize>().copied().unwrap_or_default();
+                    entity.insert(size);
+                    TokenEdit::SetSize(old)
+                }
+                TokenEdit::SetLayer(layer) => {
+                    let mut token = entity.get_mut::<Token>()?;
+                    let old = token.layer;
+                    token.layer = layer;
+                    TokenEdit::SetLayer(old)
+                }
+                TokenEdit::SetLocked(locked) => {
+                    let old = entity.contains::<TokenLocked>();
+                    if locked {
+                        entity.insert(TokenLocked);
+                    } else {
+                        entity.remove::<TokenLocked>();
+                    }
+                    TokenEdit::SetLocked(old)
+                }
+            };
+
+            Edit::EditToken {
+                token,
+                edit: old_edit,
+            }
+        }
+        Edit::UpdateBar { bar, update } => {
+            let bar = resolve(&respawned, bar);
+            let current = world.get::<ResourceBar>(bar)?.clone();
+
+            // Only the GM touches GM only bars
+            let secret =
+                current.visibility == BarVisibility::Gm || update.visibility == BarVisibility::Gm;
+            if !can_control_token(world, current.token) || (secret && !roles.is_gm(client_id)) {
+                return None;
+            }
+
+            *world.get_mut::<ResourceBar>(bar)? = ResourceBar {
+                token: current.token,
+                value: current.value,
+                ..update
+            };
+
+            Edit::UpdateBar {
+                bar,
+                update: current,
+            }
+        }
+        Edit::AdjustBar { bar, delta } => {
+            let bar = resolve(&respawned, bar);
+            let current = world.get::<ResourceBar>(bar)?;
+
+            let secret = current.visibility == BarVisibility::Gm;
+            if !can_control_token(world, current.token) || (secret && !roles.is_gm(client_id)) {
+                return None;
+            }
+
+            let mut current = world.get_mut::<ResourceBar>(bar)?;
+            current.value = current.value.saturating_add(delta);
+
+            Edit::AdjustBar {
+                bar,
+                delta: delta.saturating_neg(),
+            }
+        }
+        Edit::Despawn(entities) => {
+            if !roles.client_role(client_id).allows(permission) {
+                return None;
+            }
+
+            let mut entities = entities
+                .into_iter()
+                .map(|entity| resolve(&respawned, entity))
+                .filter(|entity| world.get_entity(*entity).is_some())
+                .collect::<Vec<_>>();
+
+            if entities.is_empty() {
+                return None;
+            }
+
+            // Bars don't make sense without their token
+            let mut bars = world.query::<(Entity, &ResourceBar)>();
+            let token_bars = bars
+                .iter(world)
+                .filter(|(_, bar)| entities.contains(&bar.token))
+                .map(|(entity, _)| entity)
+                .collect::<Vec<_>>();
+            entities.extend(token_bars);
+
+            let saved = save_entities(world, &entities);
+            for entity in entities {
+                world.entity_mut(entity).despawn_recursive();
+            }
+
+            Edit::Respawn(saved)
+        }
+        Edit::Respawn(saved) => {
+            if !roles.client_role(client_id).allows(permission) {
+                return None;
+            }
+
+            let spawned = spawn_saved_entities(world, saved, |entity| resolve(&respawned, entity));
+
+            let entities = spawned.values().copied().collect();
+            world.resource_mut::<History>().respawned.extend(spawned);
+
+            Edit::Despawn(entities)
+        }
+    };
+
+    Some(Entry { edit, permission })
+}
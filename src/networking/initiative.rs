@@ -0,0 +1,310 @@
+use crate::{
+    networking::{
+        autosave::SpawnDefaults,
+        scenes::{client_scene, ClientScenes},
+        tokens::can_control,
+        vision::VisibleTo,
+    },
+    prelude::*,
+};
+use lightyear::prelude::{server::*, *};
+use rand::Rng;
+
+pub struct InitiativePlugin;
+impl Plugin for InitiativePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(NetworkingState::Started),
+            spawn_tracker.run_if(run_once()).in_set(SpawnDefaults),
+        )
+        .add_systems(
+            Update,
+            (handle_initiative_requests, sync_combatants)
+                .chain()
+                .run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// Tracker of the first scene, the scene is assigned once it exists
+fn spawn_tracker(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Combat tracker"),
+        Initiative::default(),
+        server::Replicate {
+            target: ReplicationTarget {
+                target: NetworkTarget::None,
+            },
+            ..default()
+        },
+    ));
+}
+
+/// Announced instead of the name of a token the player can't see
+const HIDDEN_COMBATANT: &str = "Someone";
+
+fn token_name(name: Option<&TokenName>) -> String {
+    name.map(|name| name.0.clone())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("Token"))
+}
+
+/// Tells clients on the tracker's scene whose turn it is. Those who can't see the token
+/// aren't told its name
+fn announce_turn(
+    initiative: &Initiative,
+    audience: &[u64],
+    seen_by: Option<&VisibleTo>,
+    connection: &mut ConnectionManager,
+) {
+    let Some(active) = initiative.active() else {
+        return;
+    };
+
+    let (seeing, unseeing): (Vec<u64>, Vec<u64>) = audience
+        .iter()
+        .copied()
+        .partition(|client| seen_by.is_some_and(|seen_by| seen_by.contains(client)));
+
+    for (clients, name) in [
+        (seeing, active.name.clone()),
+        (unseeing, String::from(HIDDEN_COMBATANT)),
+    ] {
+        if clients.is_empty() {
+            continue;
+        }
+
+        let message = ChatMessage::Turn {
+            round: initiative.round,
+            name,
+        };
+        let target = NetworkTarget::Only(clients.into_iter().map(ClientId::from_bits).collect());
+        _ = connection.send_message_to_target::<UnorderedReliable, _>(&message, target);
+    }
+}
+
+fn handle_initiative_requests(
+    mut requests: EventReader<MessageEvent<InitiativeMessage>>,
+    mut trackers: Query<(&mut Initiative, Option<&InScene>)>,
+    tokens: Query<
+        (
+            Option<&TokenOwners>,
+            Option<&TokenName>,
+            Option<&InScene>,
+            Option<&VisibleTo>,
+        ),
+        With<Token>,
+    >,
+    scenes: Query<(Entity, &TableScene)>,
+    client_scenes: Res<ClientScenes>,
+    mut connection: ResMut<ConnectionManager>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+
+        // Each scene has its own tracker, requests go to the one the client is looking at
+        let scene = client_scene(scenes.iter(), client_id.to_bits());
+        let Some((mut initiative, _)) = trackers
+            .iter_mut()
+            .find(|(_, in_scene)| in_scene.map(|in_scene| in_scene.0) == scene)
+        else {
+            continue;
+        };
+
+        let controls = |entity: &Entity| {
+            tokens.get(*entity).is_ok_and(|(owners, ..)| {
+                can_control(&roles, client_id, Permission::EditTokens, owners)
+            })
+        };
+
+        // Players handle their own tokens and may end their own turn, the rest is up to the GM
+        let allowed = roles.is_gm(client_id)
+            || match &request.message {
+                InitiativeMessage::Add(entities) | InitiativeMessage::Roll(entities) => {
+                    entities.iter().all(controls)
+                }
+                InitiativeMessage::Remove(entity) | InitiativeMessage::Set(entity, _) => {
+                    controls(entity)
+                }
+                InitiativeMessage::Next => initiative
+                    .active()
+                    .is_some_and(|active| controls(&active.token)),
+                _ => false,
+            };
+
+        if !allowed {
+            info!(
+                "Rejected {:?} from client {}",
+                request.message,
+                client_id.to_bits()
+            );
+            continue;
+        }
+
+        let mut turn_changed = false;
+        match &request.message {
+            InitiativeMessage::Add(entities) => {
+                for entity in entities {
+                    let Ok((_, name, token_scene, _)) = tokens.get(*entity) else {
+                        continue;
+                    };
+
+                    if token_scene.map(|token_scene| token_scene.0) != scene {
+                        continue;
+                    }
+
+                    if initiative
+                        .entries
+                        .iter()
+                        .any(|entry| entry.token == *entity)
+                    {
+                        continue;
+                    }
+
+                    initiative.entries.push(InitiativeEntry {
+                        token: *entity,
+                        name: token_name(name),
+                        initiative: None,
+                    });
+                }
+            }
+            InitiativeMessage::Remove(entity) => remove_entry(&mut initiative, *entity),
+            InitiativeMessage::Set(entity, value) => {
+                for entry in initiative.entries.iter_mut() {
+                    if entry.token == *entity {
+                        entry.initiative = Some(*value);
+                    }
+                }
+            }
+            InitiativeMessage::Roll(entities) => {
+                let mut rng = rand::thread_rng();
+                for entry in initiative.entries.iter_mut() {
+                    if entities.contains(&entry.token) {
+                        entry.initiative = Some(rng.gen_range(1..=20));
+                    }
+                }
+            }
+            InitiativeMessage::Sort => {
+                let active = initiative.active().map(|active| active.token);
+
+                // Tokens without initiative go last
+                initiative
+                    .entries
+                    .sort_by_key(|entry| std::cmp::Reverse(entry.initiative.unwrap_or(i32::MIN)));
+
+                if let Some(active) = active {
+                    initiative.turn = initiative
+                        .entries
+                        .iter()
+                        .position(|entry| entry.token == active)
+                        .unwrap_or_default();
+                }
+            }
+            InitiativeMessage::Start => {
+                if initiative.entries.is_empty() {
+                    continue;
+                }
+
+                initiative.round = 1;
+                initiative.turn = 0;
+                turn_changed = true;
+            }
+            InitiativeMessage::Next => {
+                if initiative.round == 0 || initiative.entries.is_empty() {
+                    continue;
+                }
+
+                initiative.turn += 1;
+                if initiative.turn >= initiative.entries.len() {
+                    initiative.turn = 0;
+                    initiative.round += 1;
+                }
+                turn_changed = true;
+            }
+            InitiativeMessage::Previous => {
+                if initiative.round == 0 || initiative.entries.is_empty() {
+                    continue;
+                }
+
+                if initiative.turn > 0 {
+                    initiative.turn -= 1;
+                } else if initiative.round > 1 {
+                    initiative.round -= 1;
+                    initiative.turn = initiative.entries.len() - 1;
+                }
+                turn_changed = true;
+            }
+            InitiativeMessage::End => *initiative = Initiative::default(),
+        }
+
+        if turn_changed {
+            let audience = client_scenes
+                .iter()
+                .filter(|(_, client_scene)| Some(**client_scene) == scene)
+                .map(|(client, _)| *client)
+                .collect::<Vec<_>>();
+            let seen_by = initiative
+                .active()
+                .and_then(|active| tokens.get(active.token).ok())
+                .and_then(|(.., visible_to)| visible_to);
+            announce_turn(&initiative, &audience, seen_by, &mut connection);
+        }
+    }
+}
+
+/// Keeps the turn on the same token when an entry before it goes away
+fn remove_entry(initiative: &mut Initiative, token: Entity) {
+    let Some(index) = initiative
+        .entries
+        .iter()
+        .position(|entry| entry.token == token)
+    else {
+        return;
+    };
+
+    initiative.entries.remove(index);
+
+    if index < initiative.turn {
+        initiative.turn -= 1;
+    }
+    if initiative.turn >= initiative.entries.len() {
+        initiative.turn = 0;
+    }
+    if initiative.entries.is_empty() {
+        initiative.round = 0;
+    }
+}
+
+/// Follows renamed tokens and drops deleted ones
+fn sync_combatants(
+    mut trackers: Query<&mut Initiative>,
+    tokens: Query<Option<&TokenName>, With<Token>>,
+) {
+    for mut initiative in trackers.iter_mut() {
+        let removed = initiative
+            .entries
+            .iter()
+            .filter(|entry| tokens.get(entry.token).is_err())
+            .map(|entry| entry.token)
+            .collect::<Vec<_>>();
+
+        for token in removed {
+            remove_entry(&mut initiative, token);
+        }
+
+        let renamed = initiative
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                let name = token_name(tokens.get(entry.token).ok().flatten());
+                (name != entry.name).then_some((i, name))
+            })
+            .collect::<Vec<_>>();
+
+        for (i, name) in renamed {
+            initiative.entries[i].name = name;
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::path::PathBuf;
+
+use crate::{
+    networking::{
+        asset_sharing::{Sharable, SharableImage, SharedAssets},
+        bars::BarAudience,
+        history::{Edit, RecordEdit},
+        tokens::spawn_token,
+        versioned_file::{FileFormat, MAX_FILE_SIZE},
+    },
+    prelude::*,
+};
+use bevy::render::render_asset::RenderAssetUsages;
+use lightyear::prelude::{server::*, *};
+
+pub struct LibraryPlugin;
+impl Plugin for LibraryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TokenLibrary>()
+            .add_event::<LibraryRequest>()
+            .add_systems(Startup, load_library)
+            .add_systems(
+                Update,
+                handle_library_requests.run_if(in_state(NetworkingState::Started)),
+            );
+    }
+}
+
+/// Library lives on the host's disk, next to the saved sessions
+const LIBRARY_PATH: &str = "library/tokens.library";
+
+/// Version is bumped whenever [`LibraryFile`] changes
+const LIBRARY_FORMAT: FileFormat = FileFormat {
+    magic: *b"VTTL",
+    version: 2,
+    kind: "token library",
+    max_size: MAX_FILE_SIZE,
+};
+
+#[derive(Serialize, Deserialize)]
+struct LibraryFile {
+    entries: Vec<(TokenTemplate, SharableImage)>,
+}
+
+/// Everything a token placed from the library starts with, besides its image
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenTemplate {
+    pub name: String,
+    pub size: TokenSize,
+    pub conditions: Vec<Condition>,
+    /// Bars given to every placed token, the token they point to is filled in on placement
+    pub bars: Vec<ResourceBar>,
+}
+
+#[derive(Debug)]
+pub struct LibraryEntry {
+    pub id: Uuid,
+    pub template: TokenTemplate,
+    /// Loaded for thumbnails, players only get it once a token is placed with it
+    pub image: Handle<Image>,
+    /// UUID the image is shared under, once it's on the table
+    shared: Option<Uuid>,
+}
+
+/// Tokens the host keeps between sessions
+#[derive(Resource, Debug, Default)]
+pub struct TokenLibrary {
+    pub entries: Vec<LibraryEntry>,
+}
+
+/// Library change asked for from the UI, handled once the frame is over
+#[derive(Event, Debug, Clone)]
+pub enum LibraryRequest {
+    /// Keeps a token on the table as a template
+    Add(Entity),
+    Remove(Uuid),
+    Place {
+        entry: Uuid,
+        position: Vec2,
+    },
+}
+
+fn load_library(mut library: ResMut<TokenLibrary>, mut images: ResMut<Assets<Image>>) {
+    let path = PathBuf::from(LIBRARY_PATH);
+    if !path.exists() {
+        return;
+    }
+
+    let file = match LIBRARY_FORMAT.read::<LibraryFile>(&path) {
+        Ok(file) => file,
+        Err(err) => {
+            error!("Failed to load token library from {path:?}: {err}");
+            return;
+        }
+    };
+
+    // Host keeps library images around, to share them once they're placed
+    library.entries = file
+        .entries
+        .into_iter()
+        .map(|(template, image)| LibraryEntry {
+            id: Uuid::new_v4(),
+            template,
+            image: images.add(image.to_image(RenderAssetUsages::default())),
+            shared: None,
+        })
+        .collect();
+}
+
+fn save_library(library: &TokenLibrary, images: &Assets<Image>) -> Result<(), String> {
+    let entries = library
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let image = images.get(&entry.image)?.to_sharable()?;
+            Some((entry.template.clone(), image))
+        })
+        .collect();
+
+    LIBRARY_FORMAT.write(&PathBuf::from(LIBRARY_PATH), &LibraryFile { entries })
+}
+
+fn handle_library_requests(world: &mut World) {
+    let requests = world
+        .resource_mut::<Events<LibraryRequest>>()
+        .drain()
+        .collect::<Vec<_>>();
+
+    for request in requests {
+        let result = match request {
+            LibraryRequest::Add(token) => add_to_library(world, token),
+            LibraryRequest::Remove(id) => {
+                world
+                    .resource_mut::<TokenLibrary>()
+                    .entries
+                    .retain(|entry| entry.id != id);
+                Ok(())
+            }
+            LibraryRequest::Place { entry, position } => {
+                place_from_library(world, entry, position);
+                continue;
+            }
+        };
+
+        let saved = result.and_then(|_| {
+            save_library(
+                world.resource::<TokenLibrary>(),
+                world.resource::<Assets<Image>>(),
+            )
+        });
+
+        if let Err(err) = saved {
+            error!("Failed to update token library: {err}");
+        }
+    }
+}
+
+fn add_to_library(world: &mut World, token: Entity) -> Result<(), String> {
+    let mut tokens = world.query::<(
+        Option<&TokenName>,
+        Option<&TokenSize>,
+        Option<&TokenConditions>,
+        &SharedAsset<Image>,
+    )>();
+    let (name, size, conditions, image) = tokens
+        .get(world, token)
+        .map_err(|_| String::from("token has no image"))?;
+
+    let shared = image.id;
+    let template = TokenTemplate {
+        name: name.map(|name| name.0.clone()).unwrap_or_default(),
+        size: size.copied().unwrap_or_default(),
+        conditions: conditions
+            .map(|conditions| conditions.0.clone())
+            .unwrap_or_default(),
+        bars: Vec::new(),
+    };
+
+    let image = world
+        .resource::<SharedAssets<Image>>()
+        .id_to_handle
+        .get(&shared)
+        .cloned()
+        .ok_or_else(|| String::from("token image isn't loaded"))?;
+
+    let mut bars = world.query::<&ResourceBar>();
+    let bars = bars
+        .iter(world)
+        .filter(|bar| bar.token == token)
+        .map(|bar| ResourceBar {
+            token: Entity::PLACEHOLDER,
+            ..bar.clone()
+        })
+        .collect();
+
+    info!("Added {:?} to the token library", template.name);
+    world
+        .resource_mut::<TokenLibrary>()
+        .entries
+        .push(LibraryEntry {
+            id: Uuid::new_v4(),
+            template: TokenTemplate { bars, ..template },
+            image,
+            shared: Some(shared),
+        });
+
+    Ok(())
+}
+
+fn place_from_library(world: &mut World, id: Uuid, position: Vec2) {
+    if !position.is_finite() {
+        return;
+    }
+
+    let Some((template, image, shared)) = world
+        .resource::<TokenLibrary>()
+        .entries
+        .iter()
+        .find(|entry| entry.id == id)
+        .map(|entry| (entry.template.clone(), entry.image.clone(), entry.shared))
+    else {
+        return;
+    };
+
+    // Players only get to see library images that made it onto the table
+    let shared = shared.unwrap_or_else(|| {
+        let shared = world.resource_mut::<SharedAssets<Image>>().share(image);
+        if let Some(entry) = world
+            .resource_mut::<TokenLibrary>()
+            .entries
+            .iter_mut()
+            .find(|entry| entry.id == id)
+        {
+            entry.shared = Some(shared);
+        }
+        shared
+    });
+
+    let host = ClientId::Local(world.resource::<super::client::ClientId>().0);
+    let token = spawn_token(world, host, shared, position);
+
+    let mut entity = world.entity_mut(token);
+    entity.insert(template.size);
+    if !template.name.is_empty() {
+        entity.insert(TokenName(template.name));
+    }
+    if !template.conditions.is_empty() {
+        entity.insert(TokenConditions(template.conditions));
+    }
+
+    for bar in template.bars {
+        world.spawn((
+            Name::new(format!("{} bar", bar.label)),
+            ResourceBar { token, ..bar },
+            BarAudience::default(),
+            server::Replicate {
+                target: ReplicationTarget {
+                    target: NetworkTarget::None,
+                },
+                ..default()
+            },
+        ));
+    }
+
+    world.send_event(RecordEdit {
+        client: host,
+        permission: Permission::ManageTable,
+        undo: Edit::Despawn(vec![token]),
+    });
+}
@@ -4,6 +4,23 @@ pub mod client;
 pub mod protocol;
 pub mod shared;
 pub mod asset_sharing;
+pub mod autosave;
+pub mod bars;
+pub mod conditions;
+pub mod drawings;
+pub mod fog;
+pub mod grid;
+pub mod history;
+pub mod initiative;
+pub mod library;
+pub mod pings;
+pub mod scenes;
+pub mod session;
+pub mod templates;
+pub mod tokens;
+pub mod versioned_file;
+pub mod vision;
+pub mod walls;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod server;
 
@@ -24,6 +41,29 @@ impl Plugin for NetworkingPlugin {
         #[cfg(not(target_arch = "wasm32"))]
         app.add_plugins(server::ServerPlugin);
 
+        // Requests are only ever handled by the host
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins((
+            tokens::TokenPlugin,
+            grid::GridPlugin,
+            fog::FogPlugin,
+            templates::TemplatePlugin,
+            vision::VisionPlugin,
+            walls::WallPlugin,
+            drawings::DrawingPlugin,
+            pings::PingPlugin,
+            conditions::ConditionPlugin,
+            bars::BarPlugin,
+            initiative::InitiativePlugin,
+            (
+                scenes::ScenePlugin,
+                session::SessionPlugin,
+                autosave::AutosavePlugin,
+                history::HistoryPlugin,
+                library::LibraryPlugin,
+            ),
+        ));
+
         app.add_plugins(protocol::ProtocolPlugin);
     }
 }
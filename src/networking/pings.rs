@@ -0,0 +1,50 @@
+use crate::{networking::scenes::ClientScenes, prelude::*};
+use lightyear::prelude::{server::*, *};
+
+pub struct PingPlugin;
+impl Plugin for PingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            handle_ping_requests.run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+fn handle_ping_requests(
+    mut requests: EventReader<MessageEvent<PingMessage>>,
+    mut connection: ResMut<ConnectionManager>,
+    client_scenes: Res<ClientScenes>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+
+        if !roles.client_role(client_id).allows(Permission::Annotate) {
+            info!("Client {} is not allowed to ping", client_id.to_bits());
+            continue;
+        }
+
+        if !request.message.position.is_finite() {
+            continue;
+        }
+
+        let message = PingedMessage {
+            client: client_id.to_bits(),
+            position: request.message.position,
+            focus: request.message.focus && roles.is_gm(client_id),
+        };
+
+        // Pings, and the camera jump of a focus ping, stay on the pinger's scene
+        let scene = client_scenes.get(&client_id.to_bits());
+        let target = NetworkTarget::Only(
+            client_scenes
+                .iter()
+                .filter(|(_, client_scene)| Some(*client_scene) == scene)
+                .map(|(client, _)| ClientId::from_bits(*client))
+                .collect(),
+        );
+
+        _ = connection.send_message_to_target::<UnorderedReliable, _>(&message, target);
+    }
+}
@@ -10,21 +10,91 @@ impl Plugin for ProtocolPlugin {
         app.add_message::<SendMessage>(ChannelDirection::ClientToServer);
         app.add_message::<ChatMessage>(ChannelDirection::ServerToClient);
         app.add_message::<DeselectMessage>(ChannelDirection::ServerToClient);
+        app.add_message::<MoveTokenMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<EditTokenMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<SetGridMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<TemplateMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<FogMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<WallMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<DrawMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<PingMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<SetConditionCatalogueMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<BarMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<InitiativeMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<SceneMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<HistoryMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<TokenMessage>(ChannelDirection::ClientToServer);
+        app.add_message::<PingedMessage>(ChannelDirection::ServerToClient);
         app.add_message::<Player>(ChannelDirection::ClientToServer);
 
         app.register_resource::<PlayerData>(ChannelDirection::ServerToClient);
         app.register_resource::<ConnectedClients>(ChannelDirection::ServerToClient);
+        app.register_resource::<PlayerRoles>(ChannelDirection::ServerToClient);
+        app.register_resource::<ConditionCatalogue>(ChannelDirection::ServerToClient);
 
         app.register_component::<Cursor>(ChannelDirection::Bidirectional);
+        app.register_component::<Ruler>(ChannelDirection::Bidirectional);
         app.register_component::<Owner>(ChannelDirection::ServerToClient);
         app.register_component::<Token>(ChannelDirection::ServerToClient);
+        app.register_component::<TokenOwners>(ChannelDirection::ServerToClient);
+        app.register_component::<TokenConditions>(ChannelDirection::ServerToClient);
+        app.register_component::<TokenName>(ChannelDirection::ServerToClient);
+        app.register_component::<TokenSize>(ChannelDirection::ServerToClient);
+        app.register_component::<TokenLocked>(ChannelDirection::ServerToClient);
+        app.register_component::<Initiative>(ChannelDirection::ServerToClient);
+        app.add_component_map_entities::<Initiative>();
+        app.register_component::<TableScene>(ChannelDirection::ServerToClient);
+        app.register_component::<MapBackground>(ChannelDirection::ServerToClient);
+        app.register_component::<GridSettings>(ChannelDirection::ServerToClient);
+        app.register_component::<Template>(ChannelDirection::ServerToClient);
+        app.register_component::<FogOfWar>(ChannelDirection::ServerToClient);
+        app.register_component::<Sight>(ChannelDirection::ServerToClient);
+        app.register_component::<Wall>(ChannelDirection::ServerToClient);
+        app.register_component::<Stroke>(ChannelDirection::ServerToClient);
+        app.register_component::<ResourceBar>(ChannelDirection::ServerToClient);
+        app.add_component_map_entities::<ResourceBar>();
 
         app.register_type::<Token>();
         app.register_type::<Cursor>();
+        app.register_type::<Ruler>();
         app.register_type::<Replicated>();
         app.register_type::<Owner>();
+        app.register_type::<TokenOwners>();
+        app.register_type::<TokenConditions>();
+        app.register_type::<TokenName>();
+        app.register_type::<TokenSize>();
+        app.register_type::<TokenLocked>();
+        app.register_type::<Initiative>();
+        app.register_type::<Role>();
+        app.register_type::<TableScene>();
+        app.register_type::<MapBackground>();
+        app.register_type::<GridSettings>();
+        app.register_type::<Template>();
+        app.register_type::<FogOfWar>();
+        app.register_type::<Sight>();
+        app.register_type::<Wall>();
+        app.register_type::<Stroke>();
+        app.register_type::<ResourceBar>();
+        app.register_type::<SceneMessage>()
+            .add_map_entities::<SceneMessage>();
+        app.register_type::<InitiativeMessage>()
+            .add_map_entities::<InitiativeMessage>();
+        app.register_type::<BarMessage>()
+            .add_map_entities::<BarMessage>();
+        app.register_type::<DrawMessage>()
+            .add_map_entities::<DrawMessage>();
+        app.register_type::<WallMessage>()
+            .add_map_entities::<WallMessage>();
+        app.register_type::<TemplateMessage>()
+            .add_map_entities::<TemplateMessage>();
         app.register_type::<DeselectMessage>()
             .add_map_entities::<DeselectMessage>();
+        app.register_type::<MoveTokenMessage>()
+            .add_map_entities::<MoveTokenMessage>();
+        app.register_type::<EditTokenMessage>()
+            .add_map_entities::<EditTokenMessage>();
+        app.register_type::<TokenMessage>()
+            .add_map_entities::<TokenMessage>();
 
         app.add_shared_asset::<Image>();
 
@@ -33,6 +103,11 @@ impl Plugin for ProtocolPlugin {
             ..default()
         });
 
+        app.add_channel::<OrderedReliable>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        });
+
         app.add_channel::<SequencedUnreliable>(ChannelSettings {
             mode: ChannelMode::SequencedUnreliable,
             ..default()
@@ -48,6 +123,10 @@ impl Plugin for ProtocolPlugin {
 #[derive(Channel)]
 pub struct UnorderedReliable;
 
+/// For messages that only make sense in the order they were sent, like the start and end of a drag
+#[derive(Channel)]
+pub struct OrderedReliable;
+
 #[derive(Channel)]
 pub struct SequencedUnreliable;
 
@@ -60,6 +139,124 @@ pub struct Token {
     pub layer: f32,
 }
 
+/// Group of maps, tokens, walls and drawings with its own grid.
+/// Players see the active scene, unless the GM parked them on another one
+#[derive(Component, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableScene {
+    pub name: String,
+    pub active: bool,
+    /// Clients kept on this scene whatever the active one is
+    pub parked: Vec<u64>,
+}
+
+/// Scene an entity belongs to. Only used by the host and never replicated,
+/// clients only ever receive entities of their own scene
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Deref)]
+pub struct InScene(pub Entity);
+
+#[derive(Debug, Reflect, Clone, Serialize, Deserialize)]
+pub enum SceneMessage {
+    Create(String),
+    Rename(Entity, String),
+    /// Copies the scene with everything in it
+    Duplicate(Entity),
+    /// Moves every player to the scene
+    Activate(Entity),
+    /// Keeps the client on a scene, or lets them follow the active one again
+    Park(u64, Option<Entity>),
+}
+
+impl MapEntities for SceneMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        match self {
+            SceneMessage::Rename(entity, _)
+            | SceneMessage::Duplicate(entity)
+            | SceneMessage::Activate(entity)
+            | SceneMessage::Park(_, Some(entity)) => {
+                *entity = entity_mapper.map_entity(*entity);
+            }
+            SceneMessage::Create(_) | SceneMessage::Park(_, None) => (),
+        }
+    }
+}
+
+/// Image the table is played on
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapBackground {
+    pub position: Vec2,
+    /// Size on the tabletop, in grid units
+    pub size: Vec2,
+    /// Stacking order between maps, always under the grid
+    pub layer: f32,
+}
+
+impl MapBackground {
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.position.extend(self.layer)).with_scale(self.size.extend(1.0))
+    }
+}
+
+/// Grid everything on the tabletop snaps to
+#[derive(Component, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridSettings {
+    pub kind: GridKind,
+    /// Distance between centers of neighbouring cells
+    pub cell_size: f32,
+    pub origin: Vec2,
+    pub color: [u8; 3],
+    pub opacity: f32,
+    pub snap: SnapMode,
+    /// In-game distance of one cell, e.g. 5 ft
+    pub cell_distance: f32,
+    pub distance_unit: String,
+    pub diagonals: DiagonalRule,
+}
+
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridKind {
+    #[default]
+    Square,
+    HexPointy,
+    HexFlat,
+    Gridless,
+}
+
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapMode {
+    #[default]
+    Centers,
+    Intersections,
+    Disabled,
+}
+
+/// How diagonal steps are counted when measuring on a square grid
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiagonalRule {
+    /// Straight line distance
+    Euclidean,
+    /// Every diagonal step costs one cell
+    #[default]
+    Chebyshev,
+    /// Every second diagonal step costs two cells (5-10-5)
+    Alternating,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            kind: GridKind::Square,
+            cell_size: 1.0,
+            origin: Vec2::ZERO,
+            color: [0; 3],
+            opacity: 1.0,
+            snap: SnapMode::Centers,
+            cell_distance: 5.0,
+            distance_unit: String::from("ft"),
+            diagonals: DiagonalRule::Chebyshev,
+        }
+    }
+}
+
 #[derive(Component, Reflect, Debug, Clone, Serialize, Deserialize, Deref, DerefMut)]
 pub struct SharedAsset<T> {
     #[deref]
@@ -95,9 +292,100 @@ pub struct Player {
     pub color: [u8; 3],
 }
 
+/// Roles of players, assigned by the host
+#[derive(Debug, Resource, Default, Serialize, Deserialize, Clone, Deref, DerefMut)]
+pub struct PlayerRoles(pub HashMap<u64, Role>);
+
+impl PlayerRoles {
+    pub fn role(&self, client: u64) -> Role {
+        self.get(&client).copied().unwrap_or_default()
+    }
+
+    /// Role of a client as seen by the server. Host is always the GM
+    pub fn client_role(&self, client_id: ClientId) -> Role {
+        match client_id {
+            ClientId::Local(_) => Role::Gm,
+            _ => self.role(client_id.to_bits()),
+        }
+    }
+
+    pub fn is_gm(&self, client_id: ClientId) -> bool {
+        self.client_role(client_id) == Role::Gm
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Role {
+    Gm,
+    #[default]
+    Player,
+    Spectator,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Gm, Role::Player, Role::Spectator];
+
+    pub fn allows(self, permission: Permission) -> bool {
+        match self {
+            Role::Gm => true,
+            Role::Player => !matches!(permission, Permission::ManageTable),
+            Role::Spectator => matches!(permission, Permission::Chat | Permission::RequestAssets),
+        }
+    }
+
+    /// Only owners of a token and the GM may move or edit it
+    pub fn can_control(
+        self,
+        client: u64,
+        permission: Permission,
+        owners: Option<&TokenOwners>,
+    ) -> bool {
+        let is_owner = owners.is_some_and(|owners| owners.contains(&client));
+        self == Role::Gm || (self.allows(permission) && is_owner)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Gm => "GM",
+            Role::Player => "Player",
+            Role::Spectator => "Spectator",
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gm" => Ok(Role::Gm),
+            "player" => Ok(Role::Player),
+            "spectator" => Ok(Role::Spectator),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Actions that are checked against player's [`Role`] by the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Chat,
+    RequestAssets,
+    MoveTokens,
+    EditTokens,
+    /// Placing templates and drawing on the table
+    Annotate,
+    /// Everything only GM can do: giving away tokens, assigning roles, changing the table
+    ManageTable,
+}
+
 #[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize, PartialEq, Deref, DerefMut)]
 pub struct Owner(pub u64);
 
+/// Clients allowed to move and edit a token. Tokens nobody owns can only be touched by the GM
+#[derive(Component, Debug, Clone, Default, Reflect, Serialize, Deserialize, PartialEq, Deref, DerefMut)]
+pub struct TokenOwners(pub HashSet<u64>);
+
 #[derive(Component, Clone, Copy, Reflect, Serialize, Deserialize, Debug, Default, PartialEq)]
 pub struct Cursor {
     pub position: Vec2,
@@ -118,6 +406,650 @@ impl MapEntities for DeselectMessage {
     }
 }
 
+/// Token drag requests. Server validates them and writes the result into [`Token::position`]
+#[derive(Debug, Reflect, Clone, Copy, Serialize, Deserialize)]
+pub enum MoveTokenMessage {
+    Start(Entity),
+    Update(Entity, Vec2),
+    Commit(Entity, Vec2),
+    Cancel(Entity),
+}
+
+impl MoveTokenMessage {
+    pub fn entity(&self) -> Entity {
+        match *self {
+            MoveTokenMessage::Start(entity)
+            | MoveTokenMessage::Update(entity, _)
+            | MoveTokenMessage::Commit(entity, _)
+            | MoveTokenMessage::Cancel(entity) => entity,
+        }
+    }
+}
+
+impl MapEntities for MoveTokenMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        match self {
+            MoveTokenMessage::Start(entity)
+            | MoveTokenMessage::Update(entity, _)
+            | MoveTokenMessage::Commit(entity, _)
+            | MoveTokenMessage::Cancel(entity) => *entity = entity_mapper.map_entity(*entity),
+        }
+    }
+}
+
+/// Request to change properties of a token. Server checks ownership before applying it
+#[derive(Debug, Reflect, Clone, Serialize, Deserialize)]
+pub struct EditTokenMessage {
+    pub entity: Entity,
+    pub edit: TokenEdit,
+}
+
+#[derive(Debug, Reflect, Clone, Serialize, Deserialize)]
+pub enum TokenEdit {
+    SetOwners(HashSet<u64>),
+    /// Sight radius in world units, zero makes the token blind
+    SetSight(f32),
+    SetConditions(Vec<Condition>),
+    Rename(String),
+    /// Any image that is already shared
+    SetImage(Uuid),
+    SetSize(TokenSize),
+    SetLayer(f32),
+    SetLocked(bool),
+}
+
+impl TokenEdit {
+    /// What it takes to make the edit. Only the GM may do what the owners of a token may not
+    pub fn permission(&self) -> Permission {
+        match self {
+            TokenEdit::SetConditions(_) | TokenEdit::Rename(_) | TokenEdit::SetImage(_) => {
+                Permission::EditTokens
+            }
+            // Otherwise players could see through the fog by giving themselves more sight
+            TokenEdit::SetOwners(_)
+            | TokenEdit::SetSight(_)
+            | TokenEdit::SetSize(_)
+            | TokenEdit::SetLayer(_)
+            | TokenEdit::SetLocked(_) => Permission::ManageTable,
+        }
+    }
+}
+
+/// GM request to put a new token on the table, or to copy or remove one along with its bars
+#[derive(Debug, Reflect, Clone, Copy, Serialize, Deserialize)]
+pub enum TokenMessage {
+    /// Token showing an image that is already shared
+    Spawn { image: Uuid, position: Vec2 },
+    Duplicate(Entity),
+    Delete(Entity),
+}
+
+impl MapEntities for TokenMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        match self {
+            TokenMessage::Duplicate(entity) | TokenMessage::Delete(entity) => {
+                *entity = entity_mapper.map_entity(*entity)
+            }
+            TokenMessage::Spawn { .. } => (),
+        }
+    }
+}
+
+/// Footprint of a token in grid cells
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TokenSize {
+    pub width: f32,
+    pub height: f32,
+    /// Category picked for the token, which tells apart categories sharing a footprint
+    pub category: Option<SizeCategory>,
+}
+
+impl Default for TokenSize {
+    fn default() -> Self {
+        Self::square(1.0)
+    }
+}
+
+impl TokenSize {
+    pub fn square(cells: f32) -> Self {
+        Self {
+            width: cells,
+            height: cells,
+            category: None,
+        }
+    }
+
+    fn has_footprint_of(&self, category: SizeCategory) -> bool {
+        self.width == category.cells() && self.height == category.cells()
+    }
+
+    /// Category picked for the token while the footprint still matches it. Otherwise the one
+    /// the footprint matches, where the more common Medium wins over Small
+    pub fn category(&self) -> Option<SizeCategory> {
+        self.category
+            .filter(|category| self.has_footprint_of(*category))
+            .or_else(|| {
+                SizeCategory::ALL
+                    .into_iter()
+                    .rev()
+                    .find(|category| self.has_footprint_of(*category))
+            })
+    }
+
+    /// Name of the size category, or the footprint in cells
+    pub fn label(&self) -> String {
+        match self.category() {
+            Some(category) => format!("{category:?}"),
+            None => format!("{}×{}", self.width, self.height),
+        }
+    }
+}
+
+/// Size categories of tabletop games, each taking up a square of cells
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SizeCategory {
+    Tiny,
+    Small,
+    Medium,
+    Large,
+    Huge,
+    Gargantuan,
+}
+
+impl SizeCategory {
+    pub const ALL: [SizeCategory; 6] = [
+        SizeCategory::Tiny,
+        SizeCategory::Small,
+        SizeCategory::Medium,
+        SizeCategory::Large,
+        SizeCategory::Huge,
+        SizeCategory::Gargantuan,
+    ];
+
+    /// Cells along each side of the footprint
+    pub fn cells(&self) -> f32 {
+        match self {
+            SizeCategory::Tiny => 0.5,
+            SizeCategory::Small | SizeCategory::Medium => 1.0,
+            SizeCategory::Large => 2.0,
+            SizeCategory::Huge => 3.0,
+            SizeCategory::Gargantuan => 4.0,
+        }
+    }
+}
+
+impl From<SizeCategory> for TokenSize {
+    fn from(category: SizeCategory) -> Self {
+        TokenSize {
+            category: Some(category),
+            ..TokenSize::square(category.cells())
+        }
+    }
+}
+
+/// Locked tokens can't be dragged by anyone, including the GM
+#[derive(Component, Reflect, Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenLocked;
+
+/// Name of a token shown in menus and the combat tracker
+#[derive(
+    Component, Reflect, Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Deref, DerefMut,
+)]
+pub struct TokenName(pub String);
+
+/// Status effect shown as a small icon around a token
+#[derive(Reflect, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    pub label: String,
+    /// Short text or emoji drawn inside of the icon
+    pub icon: String,
+    pub color: [u8; 3],
+}
+
+impl Condition {
+    pub fn new(label: &str, icon: &str, color: [u8; 3]) -> Self {
+        Self {
+            label: label.to_owned(),
+            icon: icon.to_owned(),
+            color,
+        }
+    }
+}
+
+#[derive(
+    Component, Reflect, Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Deref, DerefMut,
+)]
+pub struct TokenConditions(pub Vec<Condition>);
+
+/// Conditions offered in the token menu, configured by the GM for the campaign
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Deref, DerefMut)]
+pub struct ConditionCatalogue(pub Vec<Condition>);
+
+impl Default for ConditionCatalogue {
+    fn default() -> Self {
+        Self(vec![
+            Condition::new("Prone", "⬇", [160, 110, 60]),
+            Condition::new("Poisoned", "☠", [90, 170, 60]),
+            Condition::new("Concentrating", "✨", [90, 140, 230]),
+            Condition::new("Blinded", "👁", [120, 120, 120]),
+            Condition::new("Stunned", "💫", [230, 200, 60]),
+            Condition::new("Restrained", "⛓", [150, 150, 170]),
+            Condition::new("Frightened", "😱", [170, 80, 200]),
+            Condition::new("Invisible", "👻", [200, 200, 230]),
+            Condition::new("Unconscious", "💤", [60, 60, 120]),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetConditionCatalogueMessage(pub ConditionCatalogue);
+
+/// Combat tracker, one for each scene
+#[derive(Component, Reflect, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Initiative {
+    /// Combatants in turn order
+    pub entries: Vec<InitiativeEntry>,
+    /// Index of the entry whose turn it is
+    pub turn: usize,
+    /// Zero while combat hasn't started
+    pub round: u32,
+}
+
+#[derive(Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InitiativeEntry {
+    pub token: Entity,
+    /// Copy of the token name, so the tracker still reads well for tokens the player can't see
+    pub name: String,
+    pub initiative: Option<i32>,
+}
+
+impl Initiative {
+    pub fn active(&self) -> Option<&InitiativeEntry> {
+        (self.round > 0).then(|| self.entries.get(self.turn)).flatten()
+    }
+}
+
+impl MapEntities for Initiative {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        for entry in self.entries.iter_mut() {
+            entry.token = entity_mapper.map_entity(entry.token);
+        }
+    }
+}
+
+#[derive(Debug, Reflect, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InitiativeMessage {
+    Add(Vec<Entity>),
+    Remove(Entity),
+    Set(Entity, i32),
+    /// Server rolls a d20 for each token
+    Roll(Vec<Entity>),
+    /// Highest initiative first
+    Sort,
+    Start,
+    Next,
+    Previous,
+    /// Ends combat and clears the tracker
+    End,
+}
+
+impl MapEntities for InitiativeMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        match self {
+            InitiativeMessage::Add(entities) | InitiativeMessage::Roll(entities) => {
+                for entity in entities.iter_mut() {
+                    *entity = entity_mapper.map_entity(*entity);
+                }
+            }
+            InitiativeMessage::Remove(entity) | InitiativeMessage::Set(entity, _) => {
+                *entity = entity_mapper.map_entity(*entity);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Numeric attribute of a token, like hit points. Every bar is its own entity,
+/// so bars hidden from a player are never replicated to them
+#[derive(Component, Reflect, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceBar {
+    pub token: Entity,
+    pub label: String,
+    pub value: i32,
+    pub max: i32,
+    pub color: [u8; 3],
+    pub visibility: BarVisibility,
+}
+
+impl ResourceBar {
+    pub fn hit_points(token: Entity) -> Self {
+        Self {
+            token,
+            label: String::from("HP"),
+            value: 10,
+            max: 10,
+            color: [200, 50, 50],
+            visibility: BarVisibility::Everyone,
+        }
+    }
+
+    /// How full the bar is, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0 {
+            return 0.0;
+        }
+        (self.value as f32 / self.max as f32).clamp(0.0, 1.0)
+    }
+}
+
+impl MapEntities for ResourceBar {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.token = entity_mapper.map_entity(self.token);
+    }
+}
+
+/// Who can see a [`ResourceBar`]. GM sees every bar
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarVisibility {
+    #[default]
+    Everyone,
+    /// Owners of the token
+    Owner,
+    Gm,
+}
+
+impl BarVisibility {
+    pub const ALL: [BarVisibility; 3] = [
+        BarVisibility::Everyone,
+        BarVisibility::Owner,
+        BarVisibility::Gm,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BarVisibility::Everyone => "Everyone",
+            BarVisibility::Owner => "Owner",
+            BarVisibility::Gm => "GM",
+        }
+    }
+}
+
+#[derive(Debug, Reflect, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BarMessage {
+    Add(ResourceBar),
+    /// Changes everything but the value, which only changes through [`BarMessage::Adjust`]
+    Update(Entity, ResourceBar),
+    /// Adds to the current value, so quick edits from several players don't overwrite each other
+    Adjust(Entity, i32),
+    Remove(Entity),
+}
+
+impl MapEntities for BarMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        match self {
+            BarMessage::Add(bar) => bar.map_entities(entity_mapper),
+            BarMessage::Update(entity, bar) => {
+                *entity = entity_mapper.map_entity(*entity);
+                bar.map_entities(entity_mapper);
+            }
+            BarMessage::Adjust(entity, _) | BarMessage::Remove(entity) => {
+                *entity = entity_mapper.map_entity(*entity);
+            }
+        }
+    }
+}
+
+/// How far a token can see when token vision is enabled
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sight {
+    pub radius: f32,
+}
+
+impl Default for Sight {
+    fn default() -> Self {
+        Self { radius: 6.0 }
+    }
+}
+
+/// Line segment that blocks vision and movement, depending on its kind
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Wall {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub kind: WallKind,
+}
+
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WallKind {
+    #[default]
+    Wall,
+    Door(DoorState),
+    /// Can be seen through, but not walked through
+    Window,
+}
+
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoorState {
+    Open,
+    #[default]
+    Closed,
+    /// Only the GM can open it
+    Locked,
+}
+
+impl Wall {
+    pub fn blocks_sight(&self) -> bool {
+        match self.kind {
+            WallKind::Wall => true,
+            WallKind::Door(state) => state != DoorState::Open,
+            WallKind::Window => false,
+        }
+    }
+
+    pub fn blocks_movement(&self) -> bool {
+        self.kind != WallKind::Door(DoorState::Open)
+    }
+}
+
+#[derive(Debug, Reflect, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WallMessage {
+    Place(Wall),
+    Remove(Entity),
+    SetDoor(Entity, DoorState),
+}
+
+impl MapEntities for WallMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        match self {
+            WallMessage::Place(_) => (),
+            WallMessage::Remove(entity) | WallMessage::SetDoor(entity, _) => {
+                *entity = entity_mapper.map_entity(*entity);
+            }
+        }
+    }
+}
+
+impl MapEntities for EditTokenMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.entity = entity_mapper.map_entity(self.entity);
+    }
+}
+
+/// Request to ping a spot on the table
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PingMessage {
+    pub position: Vec2,
+    /// Move the camera of everyone on the scene to the ping. Only the GM can do that
+    pub focus: bool,
+}
+
+/// Ping sent by the server to everyone on the pinger's scene
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PingedMessage {
+    pub client: u64,
+    pub position: Vec2,
+    pub focus: bool,
+}
+
+/// Freehand line drawn on the table, colored by its [`Owner`]
+#[derive(Component, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stroke {
+    pub kind: StrokeKind,
+    pub points: Vec<Vec2>,
+    pub width: f32,
+}
+
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrokeKind {
+    #[default]
+    Pen,
+    /// Wide and translucent
+    Highlighter,
+}
+
+#[derive(Debug, Reflect, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DrawMessage {
+    Stroke(Stroke),
+    Remove(Vec<Entity>),
+    /// Removes every stroke of the sender
+    ClearMine,
+    ClearAll,
+}
+
+impl MapEntities for DrawMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        if let DrawMessage::Remove(entities) = self {
+            for entity in entities.iter_mut() {
+                *entity = entity_mapper.map_entity(*entity);
+            }
+        }
+    }
+}
+
+/// Area of effect template placed on the table
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    pub shape: TemplateShape,
+    pub origin: Vec2,
+    /// Direction template is facing, in radians
+    pub rotation: f32,
+    /// Length of a cone or a line, radius of a burst, side of a cube
+    pub size: f32,
+    /// Width of a line
+    pub width: f32,
+}
+
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TemplateShape {
+    #[default]
+    Cone,
+    Circle,
+    Line,
+    Square,
+}
+
+impl TemplateShape {
+    pub const ALL: [TemplateShape; 4] = [
+        TemplateShape::Cone,
+        TemplateShape::Circle,
+        TemplateShape::Line,
+        TemplateShape::Square,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TemplateShape::Cone => "Cone",
+            TemplateShape::Circle => "Burst",
+            TemplateShape::Line => "Line",
+            TemplateShape::Square => "Cube",
+        }
+    }
+}
+
+#[derive(Debug, Reflect, Clone, Copy, Serialize, Deserialize)]
+pub enum TemplateMessage {
+    /// Places a new template, optionally following a token around
+    Place(Template, Option<Entity>),
+    Update(Entity, Template),
+    Remove(Entity),
+}
+
+impl MapEntities for TemplateMessage {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        match self {
+            TemplateMessage::Place(_, Some(entity))
+            | TemplateMessage::Update(entity, _)
+            | TemplateMessage::Remove(entity) => *entity = entity_mapper.map_entity(*entity),
+            TemplateMessage::Place(_, None) => (),
+        }
+    }
+}
+
+/// Darkness covering the map, with parts of it revealed by the GM
+#[derive(Component, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FogOfWar {
+    pub enabled: bool,
+    /// Players only see what their tokens can see. Tokens they can't see are not replicated to them
+    pub vision: bool,
+    /// Corners of the fogged area. Everything outside of it is always visible
+    pub min: Vec2,
+    pub max: Vec2,
+    /// Older reveals and hides, collapsed into a mask so the fog doesn't grow forever
+    pub base: FogMask,
+    /// Reveals and hides, applied in order on top of the base mask
+    pub ops: Vec<FogOp>,
+}
+
+impl Default for FogOfWar {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vision: false,
+            min: Vec2::splat(-32.0),
+            max: Vec2::splat(32.0),
+            base: FogMask::default(),
+            ops: Vec::new(),
+        }
+    }
+}
+
+/// Pixels of the fog texture as alternating runs of hidden and revealed ones, starting with hidden.
+/// Empty mask hides everything
+#[derive(Reflect, Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FogMask(pub Vec<u32>);
+
+/// Longest brush stroke or polygon the server accepts
+pub const MAX_FOG_SHAPE_POINTS: usize = 512;
+
+#[derive(Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FogOp {
+    Reveal(FogShape),
+    Hide(FogShape),
+}
+
+#[derive(Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FogShape {
+    /// Path painted with a round brush
+    Brush { points: Vec<Vec2>, radius: f32 },
+    Polygon(Vec<Vec2>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FogMessage {
+    Apply(FogOp),
+    SetEnabled(bool),
+    SetVision(bool),
+    RevealAll,
+    HideAll,
+}
+
+/// Distance measurement a player is showing to everyone. Replicated the same way as [`Cursor`]
+#[derive(Component, Clone, Reflect, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Ruler {
+    /// Start, waypoints and end of the measured path. Empty when not measuring
+    pub points: Vec<Vec2>,
+}
+
 impl Linear for Cursor {
     fn lerp(start: &Self, other: &Self, t: f32) -> Self {
         Cursor {
@@ -138,9 +1070,26 @@ impl Default for Player {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct SendMessage(pub String);
 
+/// GM request to replace the grid settings
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SetGridMessage(pub GridSettings);
+
+/// Reverts or makes again the last edit of the sender
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryMessage {
+    Undo,
+    Redo,
+}
+
+/// Chat messages received so far, kept by every client
+#[derive(Resource, Deref, DerefMut, Default)]
+pub struct ChatHistory(pub Vec<ChatMessage>);
+
 #[derive(Debug, Reflect, Clone, Serialize, Deserialize)]
 pub enum ChatMessage {
     Message(u64, String),
     Connected(u64),
     Disconnected(u64),
+    /// Combat tracker moved on to the next token
+    Turn { round: u32, name: String },
 }
@@ -0,0 +1,320 @@
+use crate::{
+    networking::{
+        autosave::SpawnDefaults,
+        session::{save_entities, spawn_saved_entities},
+    },
+    prelude::*,
+};
+use bevy::utils::HashMap;
+use lightyear::prelude::{server::*, *};
+
+pub struct ScenePlugin;
+impl Plugin for ScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClientScenes>()
+            .add_event::<DuplicateScene>()
+            .add_systems(
+                OnEnter(NetworkingState::Started),
+                spawn_first_scene.run_if(run_once()).in_set(SpawnDefaults),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_scene_requests,
+                    duplicate_scenes,
+                    update_client_scenes,
+                    assign_scenes,
+                    update_scene_audience,
+                )
+                    .chain()
+                    .run_if(in_state(NetworkingState::Started)),
+            );
+    }
+}
+
+/// Longest name of a scene
+const MAX_SCENE_NAME: usize = 32;
+
+/// Everything that belongs to a scene
+type SceneContent = Or<(
+    With<Token>,
+    With<MapBackground>,
+    With<GridSettings>,
+    With<Wall>,
+    With<Stroke>,
+    With<Template>,
+    With<FogOfWar>,
+    With<Initiative>,
+)>;
+
+/// Scene each connected client is on
+#[derive(Resource, Debug, Default, Deref)]
+pub struct ClientScenes(HashMap<u64, Entity>);
+
+/// Clients an entity of a scene is replicated to. Tokens are handled by vision instead
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+struct SceneAudience(Vec<u64>);
+
+#[derive(Event, Debug, Clone, Copy)]
+struct DuplicateScene {
+    source: Entity,
+    copy: Entity,
+}
+
+/// Scene the client is parked on, or the active one
+pub fn client_scene<'a>(
+    scenes: impl IntoIterator<Item = (Entity, &'a TableScene)>,
+    client: u64,
+) -> Option<Entity> {
+    let mut active = None;
+
+    for (entity, scene) in scenes {
+        if scene.parked.contains(&client) {
+            return Some(entity);
+        }
+
+        if scene.active {
+            active = Some(entity);
+        }
+    }
+
+    active
+}
+
+/// Content of new scenes starts out replicated to nobody, until its audience is worked out
+fn replicate_to_nobody() -> server::Replicate {
+    server::Replicate {
+        target: ReplicationTarget {
+            target: NetworkTarget::None,
+        },
+        ..default()
+    }
+}
+
+fn spawn_scene(commands: &mut Commands, name: &str, active: bool) -> Entity {
+    let name = name.trim().chars().take(MAX_SCENE_NAME).collect::<String>();
+    let name = if name.is_empty() {
+        String::from("New scene")
+    } else {
+        name
+    };
+
+    commands
+        .spawn((
+            Name::new(format!("Scene \"{name}\"")),
+            TableScene {
+                name,
+                active,
+                parked: Vec::new(),
+            },
+            server::Replicate {
+                target: ReplicationTarget {
+                    target: NetworkTarget::All,
+                },
+                ..default()
+            },
+        ))
+        .id()
+}
+
+fn spawn_first_scene(mut commands: Commands) {
+    spawn_scene(&mut commands, "Scene 1", true);
+}
+
+fn handle_scene_requests(
+    mut commands: Commands,
+    mut requests: EventReader<MessageEvent<SceneMessage>>,
+    mut scenes: Query<(Entity, &mut TableScene)>,
+    mut duplicates: EventWriter<DuplicateScene>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+
+        if !roles.client_role(client_id).allows(Permission::ManageTable) {
+            info!(
+                "Rejected {:?} from client {}",
+                request.message,
+                client_id.to_bits()
+            );
+            continue;
+        }
+
+        match &request.message {
+            SceneMessage::Create(name) => {
+                let scene = spawn_scene(&mut commands, name, false);
+                commands.spawn((
+                    Name::new("Grid settings"),
+                    GridSettings::default(),
+                    InScene(scene),
+                    replicate_to_nobody(),
+                ));
+                commands.spawn((
+                    Name::new("Fog of war"),
+                    FogOfWar::default(),
+                    InScene(scene),
+                    replicate_to_nobody(),
+                ));
+                commands.spawn((
+                    Name::new("Combat tracker"),
+                    Initiative::default(),
+                    InScene(scene),
+                    replicate_to_nobody(),
+                ));
+            }
+            SceneMessage::Rename(entity, name) => {
+                let Ok((_, mut scene)) = scenes.get_mut(*entity) else {
+                    continue;
+                };
+
+                let name = name.trim().chars().take(MAX_SCENE_NAME).collect::<String>();
+                if !name.is_empty() {
+                    scene.name = name;
+                }
+            }
+            SceneMessage::Duplicate(entity) => {
+                let Ok((_, scene)) = scenes.get(*entity) else {
+                    continue;
+                };
+
+                let name = format!("{} (copy)", scene.name);
+                let copy = spawn_scene(&mut commands, &name, false);
+                duplicates.send(DuplicateScene {
+                    source: *entity,
+                    copy,
+                });
+            }
+            SceneMessage::Activate(entity) => {
+                if !scenes.contains(*entity) {
+                    continue;
+                }
+
+                // Everyone follows, including parked players
+                for (other, mut scene) in scenes.iter_mut() {
+                    scene.active = other == *entity;
+                    scene.parked.clear();
+                }
+            }
+            SceneMessage::Park(client, target) => {
+                if target.is_some_and(|target| !scenes.contains(target)) {
+                    continue;
+                }
+
+                for (entity, mut scene) in scenes.iter_mut() {
+                    if scene.parked.contains(client) {
+                        scene.parked.retain(|parked| parked != client);
+                    }
+
+                    if Some(entity) == *target && !scene.active {
+                        scene.parked.push(*client);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Copies everything in a scene, bars come along with their tokens
+fn duplicate_scenes(world: &mut World) {
+    let duplicates = world
+        .resource_mut::<Events<DuplicateScene>>()
+        .drain()
+        .collect::<Vec<_>>();
+
+    for duplicate in duplicates {
+        let mut copied = world
+            .query_filtered::<(Entity, &InScene), SceneContent>()
+            .iter(world)
+            .filter(|(_, scene)| scene.0 == duplicate.source)
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        let bars = world
+            .query::<(Entity, &ResourceBar)>()
+            .iter(world)
+            .filter(|(_, bar)| copied.contains(&bar.token))
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        copied.extend(bars);
+
+        let saved = save_entities(world, &copied);
+        spawn_saved_entities(world, saved, |entity| {
+            if entity == duplicate.source {
+                duplicate.copy
+            } else {
+                entity
+            }
+        });
+    }
+}
+
+fn update_client_scenes(
+    mut client_scenes: ResMut<ClientScenes>,
+    scenes: Query<(Entity, &TableScene)>,
+    clients: Res<ConnectedClients>,
+) {
+    let new_scenes = clients
+        .iter()
+        .filter_map(|client| Some((*client, client_scene(scenes.iter(), *client)?)))
+        .collect::<HashMap<_, _>>();
+
+    if client_scenes.0 != new_scenes {
+        client_scenes.0 = new_scenes;
+    }
+}
+
+/// New entities go to the scene of whoever made them, or to the host's
+fn assign_scenes(
+    mut commands: Commands,
+    entities: Query<(Entity, Option<&Owner>), (SceneContent, Without<InScene>)>,
+    scenes: Query<(Entity, &TableScene)>,
+    host: Option<Res<super::client::ClientId>>,
+) {
+    for (entity, owner) in entities.iter() {
+        let creator = owner
+            .map(|owner| owner.0)
+            .or(host.as_ref().map(|host| host.0));
+
+        let Some(scene) = creator.and_then(|creator| client_scene(scenes.iter(), creator)) else {
+            continue;
+        };
+
+        commands.entity(entity).insert(InScene(scene));
+    }
+}
+
+/// Replicates entities of a scene only to clients on that scene
+fn update_scene_audience(
+    mut commands: Commands,
+    mut entities: Query<
+        (
+            Entity,
+            &InScene,
+            Option<&SceneAudience>,
+            &mut ReplicationTarget,
+        ),
+        Without<Token>,
+    >,
+    client_scenes: Res<ClientScenes>,
+) {
+    for (entity, scene, audience, mut target) in entities.iter_mut() {
+        let mut clients = client_scenes
+            .iter()
+            .filter(|(_, client_scene)| **client_scene == scene.0)
+            .map(|(client, _)| *client)
+            .collect::<Vec<_>>();
+        clients.sort_unstable();
+
+        if audience.is_some_and(|audience| audience.0 == clients) {
+            continue;
+        }
+
+        target.target = NetworkTarget::Only(
+            clients
+                .iter()
+                .map(|client| ClientId::from_bits(*client))
+                .collect(),
+        );
+        commands.entity(entity).insert(SceneAudience(clients));
+    }
+}
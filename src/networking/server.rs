@@ -32,17 +32,24 @@ impl Plugin for ServerPlugin {
         app.add_plugins(ServerPlugins::new(config))
             .init_resource::<PlayerData>()
             .init_resource::<ConnectedClients>()
+            .init_resource::<PlayerRoles>()
             .add_systems(Startup, replicate_resources)
             .add_systems(
                 Update,
-                (recieve_message, replicate_cursors, despawn_cursors)
+                (
+                    recieve_message,
+                    replicate_client_entities,
+                    despawn_client_entities,
+                )
                     .run_if(in_state(NetworkingState::Started)),
             );
     }
 }
 
 fn replicate_resources(mut commands: Commands) {
-    commands.replicate_resource::<PlayerData, SequencedReliable>(NetworkTarget::All)
+    commands.replicate_resource::<PlayerData, SequencedReliable>(NetworkTarget::All);
+    commands.replicate_resource::<PlayerRoles, SequencedReliable>(NetworkTarget::All);
+    commands.replicate_resource::<ConditionCatalogue, SequencedReliable>(NetworkTarget::All);
 }
 
 fn recieve_message(
@@ -52,6 +59,7 @@ fn recieve_message(
     mut disconnected: EventReader<DisconnectEvent>,
     mut player_list: ResMut<PlayerData>,
     mut clients: ResMut<ConnectedClients>,
+    mut roles: ResMut<PlayerRoles>,
     mut connection: ResMut<ConnectionManager>,
 ) {
     for player_updated in player_updated.read() {
@@ -67,12 +75,20 @@ fn recieve_message(
 
         clients.insert(connected.client_id.to_bits());
 
+        let role = roles.client_role(connected.client_id);
+        roles.entry(connected.client_id.to_bits()).or_insert(role);
+
         connection
             .send_message_to_target::<UnorderedReliable, _>(&chat_message, NetworkTarget::All)
             .unwrap();
     }
 
     for message in messages.read() {
+        if !roles.client_role(message.context).allows(Permission::Chat) {
+            info!("Client {} is not allowed to chat", message.context.to_bits());
+            continue;
+        }
+
         info!(
             "Server recieved message from {}: {}",
             message.context.to_bits(),
@@ -96,12 +112,13 @@ fn recieve_message(
     }
 }
 
-fn replicate_cursors(
+/// Passes entities owned by clients (cursors, rulers) on to everyone else
+fn replicate_client_entities(
     mut commands: Commands,
     client_id: Option<Res<super::client::ClientId>>,
-    cursors: Query<(Entity, &Replicated), (With<Cursor>, Added<Replicated>)>,
+    entities: Query<(Entity, &Replicated), (Or<(With<Cursor>, With<Ruler>)>, Added<Replicated>)>,
 ) {
-    for (entity, replicated) in cursors.iter() {
+    for (entity, replicated) in entities.iter() {
         let mut entity = commands.entity(entity);
         let client_id = replicated.from.unwrap_or(ClientId::Local(client_id.as_ref().unwrap().0));
 
@@ -117,18 +134,17 @@ fn replicate_cursors(
     }
 }
 
-fn despawn_cursors(
+fn despawn_client_entities(
     mut commands: Commands,
     mut disconnected: EventReader<DisconnectEvent>,
-    cursors: Query<(Entity, &Replicated), With<Cursor>>,
+    entities: Query<(Entity, &Replicated), Or<(With<Cursor>, With<Ruler>)>>,
 ) {
     for disconnect in disconnected.read() {
         let client_id = disconnect.client_id;
 
-        info!("Despawning entity");
-
-        if let Some((cursor, _)) = cursors.iter().find(|x| x.1.client_id() == client_id) {
-            commands.entity(cursor).despawn();
+        for (entity, _) in entities.iter().filter(|x| x.1.client_id() == client_id) {
+            info!("Despawning entity");
+            commands.entity(entity).despawn();
         }
     }
 }
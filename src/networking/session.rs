@@ -0,0 +1,487 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    networking::{
+        asset_sharing::{Sharable, SharableImage, SharedAssets},
+        bars::BarAudience,
+        history::History,
+        templates::TemplateAnchor,
+        versioned_file::{FileFormat, MAX_FILE_SIZE},
+    },
+    prelude::*,
+};
+use bevy::{
+    ecs::entity::{EntityMapper, MapEntities},
+    render::render_asset::RenderAssetUsages,
+};
+use lightyear::prelude::{server::*, *};
+
+pub struct SessionPlugin;
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SessionRequest>().add_systems(
+            Update,
+            handle_session_requests.run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// Folder sessions are saved to, next to the executable's working directory
+pub const SAVE_DIR: &str = "saves";
+
+/// Version is bumped whenever [`SessionFile`] changes
+const SESSION_FORMAT: FileFormat = FileFormat {
+    magic: *b"VTTS",
+    version: 4,
+    kind: "session",
+    max_size: MAX_FILE_SIZE,
+};
+
+/// Save or load asked for from the UI, handled once the frame is over
+#[derive(Event, Debug, Clone)]
+pub enum SessionRequest {
+    Save(String),
+    Load(String),
+}
+
+/// Everything hosted sessions are made of
+#[derive(Serialize, Deserialize)]
+struct SessionFile {
+    player_data: PlayerData,
+    roles: PlayerRoles,
+    catalogue: ConditionCatalogue,
+    chat: Vec<ChatMessage>,
+    /// Shared images in use, under the UUIDs entities refer to them by
+    images: Vec<(Uuid, SharableImage)>,
+    entities: Vec<SavedEntity>,
+}
+
+/// Replicated components of an entity. Entities they point to are saved as they were,
+/// and mapped to the newly spawned ones on load
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct SavedEntity {
+    entity: Entity,
+    scene: Option<TableScene>,
+    in_scene: Option<Entity>,
+    map: Option<MapBackground>,
+    grid: Option<GridSettings>,
+    token: Option<Token>,
+    token_name: Option<TokenName>,
+    token_owners: Option<TokenOwners>,
+    token_size: Option<TokenSize>,
+    locked: bool,
+    sight: Option<Sight>,
+    conditions: Option<TokenConditions>,
+    image: Option<Uuid>,
+    wall: Option<Wall>,
+    stroke: Option<Stroke>,
+    template: Option<Template>,
+    template_anchor: Option<Entity>,
+    owner: Option<Owner>,
+    bar: Option<ResourceBar>,
+    fog: Option<FogOfWar>,
+    initiative: Option<Initiative>,
+}
+
+/// Entities that make up a session, and get replaced when one is loaded
+type SessionContent = Or<(
+    With<TableScene>,
+    With<MapBackground>,
+    With<GridSettings>,
+    With<Token>,
+    With<Wall>,
+    With<Stroke>,
+    With<Template>,
+    With<ResourceBar>,
+    With<FogOfWar>,
+    With<Initiative>,
+)>;
+
+/// Saved entities to the ones spawned for them. Anything else goes through `fallback`
+struct LoadedEntities<F> {
+    spawned: HashMap<Entity, Entity>,
+    fallback: F,
+}
+
+impl<F: FnMut(Entity) -> Entity> EntityMapper for LoadedEntities<F> {
+    fn map_entity(&mut self, entity: Entity) -> Entity {
+        match self.spawned.get(&entity) {
+            Some(spawned) => *spawned,
+            None => (self.fallback)(entity),
+        }
+    }
+}
+
+pub fn session_path(name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '));
+
+    if !valid {
+        return Err(format!(
+            "\"{name}\" is not a valid session name, use letters, numbers, spaces, - and _"
+        ));
+    }
+
+    Ok(PathBuf::from(SAVE_DIR).join(format!("{name}.session")))
+}
+
+/// Names of the sessions in [`SAVE_DIR`], sorted
+pub fn saved_sessions() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(SAVE_DIR) else {
+        return Vec::new();
+    };
+
+    let mut names = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "session" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+fn handle_session_requests(world: &mut World) {
+    let requests = world
+        .resource_mut::<Events<SessionRequest>>()
+        .drain()
+        .collect::<Vec<_>>();
+
+    for request in requests {
+        match request {
+            SessionRequest::Save(name) => match session_path(&name) {
+                Ok(path) => match save_session(world, &path) {
+                    Ok(()) => info!("Session saved to {path:?}"),
+                    Err(err) => error!("Failed to save session: {err}"),
+                },
+                Err(err) => error!("{err}"),
+            },
+            SessionRequest::Load(name) => match session_path(&name) {
+                Ok(path) => match load_session(world, &path) {
+                    Ok(()) => info!("Session loaded from {path:?}"),
+                    Err(err) => error!("Failed to load session: {err}"),
+                },
+                Err(err) => error!("{err}"),
+            },
+        }
+    }
+}
+
+/// Components of the given entities, as they'd be written to a session file
+pub(crate) fn save_entities(world: &mut World, entities: &[Entity]) -> Vec<SavedEntity> {
+    let mut query = world.query::<(
+        Entity,
+        (
+            Option<&TableScene>,
+            Option<&InScene>,
+            Option<&MapBackground>,
+            Option<&GridSettings>,
+            Option<&Token>,
+            Option<&TokenName>,
+            Option<&TokenOwners>,
+            Option<&Sight>,
+            Option<&TokenConditions>,
+            Option<&SharedAsset<Image>>,
+        ),
+        (
+            Option<&Wall>,
+            Option<&Stroke>,
+            Option<&Template>,
+            Option<&TemplateAnchor>,
+            Option<&Owner>,
+            Option<&ResourceBar>,
+            Option<&FogOfWar>,
+            Option<&Initiative>,
+            Option<&TokenSize>,
+            Has<TokenLocked>,
+        ),
+    )>();
+
+    query
+        .iter_many(world, entities)
+        .map(
+            |(
+                entity,
+                (
+                    scene,
+                    in_scene,
+                    map,
+                    grid,
+                    token,
+                    token_name,
+                    token_owners,
+                    sight,
+                    conditions,
+                    image,
+                ),
+                (
+                    wall,
+                    stroke,
+                    template,
+                    template_anchor,
+                    owner,
+                    bar,
+                    fog,
+                    initiative,
+                    token_size,
+                    locked,
+                ),
+            )| SavedEntity {
+                entity,
+                scene: scene.cloned(),
+                in_scene: in_scene.map(|in_scene| in_scene.0),
+                map: map.copied(),
+                grid: grid.cloned(),
+                token: token.copied(),
+                token_name: token_name.cloned(),
+                token_owners: token_owners.cloned(),
+                token_size: token_size.copied(),
+                locked,
+                sight: sight.copied(),
+                conditions: conditions.cloned(),
+                image: image.map(|image| image.id),
+                wall: wall.copied(),
+                stroke: stroke.cloned(),
+                template: template.copied(),
+                template_anchor: template_anchor.map(|anchor| anchor.0),
+                owner: owner.cloned(),
+                bar: bar.cloned(),
+                fog: fog.cloned(),
+                initiative: initiative.cloned(),
+            },
+        )
+        .collect()
+}
+
+fn collect_session(world: &mut World) -> SessionFile {
+    let entities = world
+        .query_filtered::<Entity, SessionContent>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    let entities = save_entities(world, &entities);
+
+    let shared_images = world.resource::<SharedAssets<Image>>();
+    let assets = world.resource::<Assets<Image>>();
+
+    let mut images = entities
+        .iter()
+        .filter_map(|saved| saved.image)
+        .collect::<Vec<_>>();
+    images.sort_unstable();
+    images.dedup();
+
+    let images = images
+        .into_iter()
+        .filter_map(|uuid| {
+            let image = assets.get(shared_images.id_to_handle.get(&uuid)?)?;
+            let Some(sharable) = image.to_sharable() else {
+                warn!("Image {uuid} can't be saved, its format isn't supported");
+                return None;
+            };
+            Some((uuid, sharable))
+        })
+        .collect();
+
+    SessionFile {
+        player_data: world.resource::<PlayerData>().clone(),
+        roles: world.resource::<PlayerRoles>().clone(),
+        catalogue: world.resource::<ConditionCatalogue>().clone(),
+        chat: world
+            .get_resource::<ChatHistory>()
+            .map(|chat| chat.0.clone())
+            .unwrap_or_default(),
+        images,
+        entities,
+    }
+}
+
+pub fn save_session(world: &mut World, path: &Path) -> Result<(), String> {
+    let session = collect_session(world);
+    SESSION_FORMAT.write(path, &session)
+}
+
+/// Nobody gets loaded entities until scenes and vision work out who should see them
+fn replicate_to_nobody() -> server::Replicate {
+    server::Replicate {
+        target: ReplicationTarget {
+            target: NetworkTarget::None,
+        },
+        ..default()
+    }
+}
+
+fn replicate_to_all() -> server::Replicate {
+    server::Replicate {
+        target: ReplicationTarget {
+            target: NetworkTarget::All,
+        },
+        ..default()
+    }
+}
+
+/// Spawns saved entities again, pointing them at each other. Entities outside of the saved ones
+/// are mapped with `fallback`. Returns which entity each saved one became
+pub(crate) fn spawn_saved_entities(
+    world: &mut World,
+    entities: Vec<SavedEntity>,
+    fallback: impl FnMut(Entity) -> Entity,
+) -> HashMap<Entity, Entity> {
+    let mut loaded = LoadedEntities {
+        spawned: entities
+            .iter()
+            .map(|saved| (saved.entity, world.spawn_empty().id()))
+            .collect(),
+        fallback,
+    };
+
+    for saved in entities {
+        let entity = loaded.map_entity(saved.entity);
+        let in_scene = saved
+            .in_scene
+            .map(|scene| InScene(loaded.map_entity(scene)));
+        let mut entity = world.entity_mut(entity);
+
+        if let Some(scene) = saved.scene {
+            entity.insert((
+                Name::new(format!("Scene \"{}\"", scene.name)),
+                scene,
+                replicate_to_all(),
+            ));
+        } else if let Some(fog) = saved.fog {
+            entity.insert((Name::new("Fog of war"), fog, replicate_to_nobody()));
+        } else if let Some(mut initiative) = saved.initiative {
+            initiative.map_entities(&mut loaded);
+            entity.insert((
+                Name::new("Combat tracker"),
+                initiative,
+                replicate_to_nobody(),
+            ));
+        } else if let Some(mut bar) = saved.bar {
+            bar.map_entities(&mut loaded);
+            entity.insert((
+                Name::new(format!("{} bar", bar.label)),
+                bar,
+                BarAudience::default(),
+                replicate_to_nobody(),
+            ));
+        } else {
+            entity.insert(replicate_to_nobody());
+        }
+
+        if let Some(in_scene) = in_scene {
+            entity.insert(in_scene);
+        }
+        if let Some(map) = saved.map {
+            entity.insert((Name::new("Map background"), map));
+        }
+        if let Some(grid) = saved.grid {
+            entity.insert((Name::new("Grid settings"), grid));
+        }
+        if let Some(token) = saved.token {
+            entity.insert((Name::new("Token"), token));
+        }
+        if let Some(token_name) = saved.token_name {
+            entity.insert(token_name);
+        }
+        if let Some(token_owners) = saved.token_owners {
+            entity.insert(token_owners);
+        }
+        if let Some(token_size) = saved.token_size {
+            entity.insert(token_size);
+        }
+        if saved.locked {
+            entity.insert(TokenLocked);
+        }
+        if let Some(sight) = saved.sight {
+            entity.insert(sight);
+        }
+        if let Some(conditions) = saved.conditions {
+            entity.insert(conditions);
+        }
+        if let Some(image) = saved.image {
+            entity.insert(SharedAsset::<Image>::new(image));
+        }
+        if let Some(wall) = saved.wall {
+            entity.insert((Name::new("Wall"), wall));
+        }
+        if let Some(stroke) = saved.stroke {
+            entity.insert((Name::new("Stroke"), stroke));
+        }
+        if let Some(template) = saved.template {
+            entity.insert((
+                Name::new(format!("{} template", template.shape.name())),
+                template,
+            ));
+        }
+        if let Some(anchor) = saved.template_anchor {
+            entity.insert(TemplateAnchor(loaded.map_entity(anchor)));
+        }
+        if let Some(owner) = saved.owner {
+            entity.insert(owner);
+        }
+    }
+
+    loaded.spawned
+}
+
+/// Replaces everything on the table with the saved session.
+/// Roles and names of players connected right now are kept
+pub fn load_session(world: &mut World, path: &Path) -> Result<(), String> {
+    let session = SESSION_FORMAT.read::<SessionFile>(path)?;
+
+    let old_entities = world
+        .query_filtered::<Entity, SessionContent>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    // Visuals of bars and conditions hang off of what they belong to
+    for entity in old_entities {
+        if let Some(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    }
+
+    world.resource_scope(|world, mut shared_images: Mut<SharedAssets<Image>>| {
+        let mut assets = world.resource_mut::<Assets<Image>>();
+
+        for (uuid, image) in session.images.iter() {
+            if shared_images.id_to_handle.contains_key(uuid) {
+                continue;
+            }
+
+            // Host keeps loaded images around, clients will ask for them
+            let image = image.to_image(RenderAssetUsages::default());
+            shared_images.add_shared_with_id(&mut assets, image, *uuid);
+        }
+    });
+
+    spawn_saved_entities(world, session.entities, |_| Entity::PLACEHOLDER);
+
+    let mut roles = world.resource_mut::<PlayerRoles>();
+    for (client, role) in session.roles.0 {
+        roles.0.entry(client).or_insert(role);
+    }
+
+    let mut player_data = world.resource_mut::<PlayerData>();
+    for (client, player) in session.player_data.0 {
+        player_data.0.entry(client).or_insert(player);
+    }
+
+    *world.resource_mut::<ConditionCatalogue>() = session.catalogue;
+
+    // Edits made before refer to entities that are gone now
+    if let Some(mut history) = world.get_resource_mut::<History>() {
+        *history = History::default();
+    }
+
+    if let Some(mut chat) = world.get_resource_mut::<ChatHistory>() {
+        chat.0 = session.chat;
+    }
+
+    Ok(())
+}
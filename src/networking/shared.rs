@@ -1,4 +1,4 @@
-use crate::prelude::*;
+use crate::{prelude::*, tabletop::Moving};
 use client::Interpolated;
 use lightyear::prelude::*;
 use std::time::Duration;
@@ -26,8 +26,9 @@ impl Plugin for SharedPlugin {
     }
 }
 
+/// Host follows the server's tokens too, so edits like undo and layer changes show up there
 fn update_token_position(
-    mut tokens: Query<(&mut Transform, &Token), Or<(With<Replicated>, With<Interpolated>)>>,
+    mut tokens: Query<(&mut Transform, &Token), Without<Moving>>,
     time: Res<Time>,
 ) {
     for (mut transform, token) in tokens.iter_mut() {
@@ -0,0 +1,113 @@
+use crate::prelude::*;
+use lightyear::prelude::{server::*, *};
+
+pub struct TemplatePlugin;
+impl Plugin for TemplatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (handle_template_requests, follow_anchors)
+                .chain()
+                .run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// Token a template is attached to. Only exists on the server
+#[derive(Component, Debug, Clone, Copy, Deref)]
+pub struct TemplateAnchor(pub Entity);
+
+fn is_valid(template: &Template) -> bool {
+    template.origin.is_finite()
+        && template.rotation.is_finite()
+        && template.size.is_finite()
+        && template.width.is_finite()
+        && template.size > 0.0
+        && template.width > 0.0
+}
+
+fn handle_template_requests(
+    mut commands: Commands,
+    mut requests: EventReader<MessageEvent<TemplateMessage>>,
+    mut templates: Query<(&mut Template, &Owner)>,
+    tokens: Query<&Token>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+        let role = roles.client_role(client_id);
+
+        if !role.allows(Permission::Annotate) {
+            info!(
+                "Client {} is not allowed to place templates",
+                client_id.to_bits()
+            );
+            continue;
+        }
+
+        match request.message {
+            TemplateMessage::Place(mut template, anchor) => {
+                if !is_valid(&template) {
+                    continue;
+                }
+
+                let anchor = anchor.and_then(|anchor| Some((anchor, tokens.get(anchor).ok()?)));
+                if let Some((_, token)) = anchor {
+                    template.origin = token.position;
+                }
+
+                let mut entity = commands.spawn((
+                    Name::new(format!("{} template", template.shape.name())),
+                    template,
+                    Owner(client_id.to_bits()),
+                    server::Replicate {
+                        target: ReplicationTarget {
+                            target: NetworkTarget::None,
+                        },
+                        ..default()
+                    },
+                ));
+
+                if let Some((anchor, _)) = anchor {
+                    entity.insert(TemplateAnchor(anchor));
+                }
+            }
+            TemplateMessage::Update(entity, new_template) => {
+                let Ok((mut template, owner)) = templates.get_mut(entity) else {
+                    continue;
+                };
+
+                if (owner.0 == client_id.to_bits() || role == Role::Gm) && is_valid(&new_template) {
+                    *template = new_template;
+                }
+            }
+            TemplateMessage::Remove(entity) => {
+                let Ok((_, owner)) = templates.get(entity) else {
+                    continue;
+                };
+
+                if owner.0 == client_id.to_bits() || role == Role::Gm {
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+    }
+}
+
+/// Keeps anchored templates on top of their tokens
+fn follow_anchors(
+    mut commands: Commands,
+    mut templates: Query<(Entity, &mut Template, &TemplateAnchor)>,
+    tokens: Query<&Token>,
+) {
+    for (entity, mut template, anchor) in templates.iter_mut() {
+        let Ok(token) = tokens.get(**anchor) else {
+            commands.entity(entity).remove::<TemplateAnchor>();
+            continue;
+        };
+
+        if template.origin != token.position {
+            template.origin = token.position;
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    networking::{
+        asset_sharing::SharedAssets,
+        conditions::truncate_condition,
+        history::{ApplyEdit, Edit, RecordEdit},
+        scenes::ClientScenes,
+        session::{save_entities, spawn_saved_entities},
+        vision::segments_intersect,
+    },
+    prelude::*,
+};
+use lightyear::prelude::{server::*, *};
+
+pub struct TokenPlugin;
+impl Plugin for TokenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DraggedTokens>().add_systems(
+            Update,
+            (
+                handle_move_requests,
+                handle_edit_requests,
+                handle_token_requests,
+                release_disconnected_drags,
+            )
+                .chain()
+                .run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// More would not fit around a token anyway
+const MAX_CONDITIONS: usize = 16;
+
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Layer new tokens are put on
+pub const DEFAULT_TOKEN_LAYER: f32 = 15.0;
+
+/// Layers tokens can be put on, between the maps and the drawings
+pub const TOKEN_LAYERS: RangeInclusive<f32> = 10.0..=19.0;
+
+/// Smallest and largest side of a token, in cells
+pub const TOKEN_SIZES: RangeInclusive<f32> = 0.25..=10.0;
+
+/// Tokens that are currently being dragged, and by whom
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct DraggedTokens(pub HashMap<Entity, Drag>);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Drag {
+    pub client: u64,
+    pub start: Vec2,
+}
+
+/// Whether the client may move or edit a token, see [`Role::can_control`]
+pub fn can_control(
+    roles: &PlayerRoles,
+    client_id: ClientId,
+    permission: Permission,
+    owners: Option<&TokenOwners>,
+) -> bool {
+    roles
+        .client_role(client_id)
+        .can_control(client_id.to_bits(), permission, owners)
+}
+
+fn handle_move_requests(
+    mut requests: EventReader<MessageEvent<MoveTokenMessage>>,
+    mut tokens: Query<(
+        &mut Token,
+        Option<&TokenOwners>,
+        Option<&InScene>,
+        Has<TokenLocked>,
+    )>,
+    walls: Query<(&Wall, Option<&InScene>)>,
+    mut dragged: ResMut<DraggedTokens>,
+    mut edits: EventWriter<RecordEdit>,
+    mut connection: ResMut<ConnectionManager>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+        let entity = request.message.entity();
+
+        let Ok((mut token, owners, scene, locked)) = tokens.get_mut(entity) else {
+            dragged.remove(&entity);
+            _ = connection.send_message::<UnorderedReliable, _>(
+                client_id,
+                &DeselectMessage::Entity(entity),
+            );
+            continue;
+        };
+
+        if !can_control(&roles, client_id, Permission::MoveTokens, owners) {
+            info!(
+                "Client {} is not allowed to move {entity:?}",
+                client_id.to_bits()
+            );
+            _ = connection.send_message::<UnorderedReliable, _>(
+                client_id,
+                &DeselectMessage::Entity(entity),
+            );
+            continue;
+        }
+
+        let drag = dragged.get(&entity).copied();
+        let is_dragging = drag.is_some_and(|drag| drag.client == client_id.to_bits());
+
+        // Players can't walk through walls
+        let blocked = |from: Vec2, to: Vec2| {
+            !roles.is_gm(client_id)
+                && walls.iter().any(|(wall, wall_scene)| {
+                    wall_scene == scene
+                        && wall.blocks_movement()
+                        && segments_intersect((from, to), (wall.start, wall.end))
+                })
+        };
+
+        let accepted = match request.message {
+            MoveTokenMessage::Start(_) => {
+                if !locked && (drag.is_none() || is_dragging) {
+                    dragged.insert(
+                        entity,
+                        Drag {
+                            client: client_id.to_bits(),
+                            start: token.position,
+                        },
+                    );
+                    true
+                } else {
+                    false
+                }
+            }
+            // Start, Commit and Cancel share an ordered channel, but updates are unreliable
+            // and may overtake the Start, so stale ones are dropped silently
+            MoveTokenMessage::Update(_, position) => {
+                if let Some(drag) = drag.filter(|_| is_dragging && position.is_finite()) {
+                    // A path bending around a wall has every step checked, not just its ends
+                    if !blocked(token.position, position) && !blocked(drag.start, position) {
+                        token.position = position;
+                    }
+                }
+                true
+            }
+            MoveTokenMessage::Commit(_, position) => {
+                if let Some(drag) = drag.filter(|_| is_dragging && position.is_finite()) {
+                    // Blocked tokens go back to where they were picked up
+                    token.position = if blocked(token.position, position)
+                        || blocked(drag.start, position)
+                    {
+                        drag.start
+                    } else {
+                        position
+                    };
+                    dragged.remove(&entity);
+
+                    if token.position != drag.start {
+                        edits.send(RecordEdit {
+                            client: client_id,
+                            permission: Permission::MoveTokens,
+                            undo: Edit::MoveToken {
+                                token: entity,
+                                position: drag.start,
+                            },
+                        });
+                    }
+                    true
+                } else {
+                    false
+                }
+            }
+            MoveTokenMessage::Cancel(_) => {
+                if let Some(drag) = drag.filter(|_| is_dragging) {
+                    token.position = drag.start;
+                    dragged.remove(&entity);
+                }
+                true
+            }
+        };
+
+        if !accepted {
+            info!(
+                "Rejected {:?} from client {}",
+                request.message,
+                client_id.to_bits()
+            );
+            _ = connection.send_message::<UnorderedReliable, _>(
+                client_id,
+                &DeselectMessage::Entity(entity),
+            );
+        }
+    }
+}
+
+/// Edits are made through the history, so they can be undone
+fn handle_edit_requests(
+    mut requests: EventReader<MessageEvent<EditTokenMessage>>,
+    tokens: Query<Option<&TokenOwners>, With<Token>>,
+    shared_images: Res<SharedAssets<Image>>,
+    mut edits: EventWriter<ApplyEdit>,
+    mut connection: ResMut<ConnectionManager>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+        let entity = request.message.entity;
+        let permission = request.message.edit.permission();
+
+        let valid = match &request.message.edit {
+            TokenEdit::SetOwners(_) | TokenEdit::SetLocked(_) => true,
+            TokenEdit::Rename(name) => name.chars().count() <= MAX_NAME_LENGTH,
+            TokenEdit::SetConditions(conditions) => conditions.len() <= MAX_CONDITIONS,
+            TokenEdit::SetSight(radius) => radius.is_finite() && *radius >= 0.0,
+            TokenEdit::SetImage(image) => shared_images.id_to_handle.contains_key(image),
+            TokenEdit::SetSize(size) => [size.width, size.height]
+                .iter()
+                .all(|side| TOKEN_SIZES.contains(side)),
+            TokenEdit::SetLayer(layer) => TOKEN_LAYERS.contains(layer),
+        };
+
+        let allowed = valid
+            && tokens
+                .get(entity)
+                .is_ok_and(|owners| can_control(&roles, client_id, permission, owners));
+
+        if !allowed {
+            info!(
+                "Rejected {:?} from client {}",
+                request.message,
+                client_id.to_bits()
+            );
+            _ = connection.send_message::<UnorderedReliable, _>(
+                client_id,
+                &DeselectMessage::Entity(entity),
+            );
+            continue;
+        }
+
+        let edit = match &request.message.edit {
+            TokenEdit::Rename(name) => TokenEdit::Rename(name.trim().to_owned()),
+            TokenEdit::SetConditions(conditions) => TokenEdit::SetConditions(
+                conditions.iter().cloned().map(truncate_condition).collect(),
+            ),
+            edit => edit.clone(),
+        };
+
+        edits.send(ApplyEdit {
+            client: client_id,
+            permission,
+            edit: Edit::EditToken {
+                token: entity,
+                edit,
+            },
+        });
+    }
+}
+
+/// GM requests to spawn, copy and delete tokens. Copies are put one cell to the right of the original
+fn handle_token_requests(world: &mut World) {
+    let requests = world
+        .resource_mut::<Events<MessageEvent<TokenMessage>>>()
+        .drain()
+        .map(|request| (request.context, request.message))
+        .collect::<Vec<_>>();
+
+    for (client_id, message) in requests {
+        if !world.resource::<PlayerRoles>().is_gm(client_id) {
+            info!("Rejected {message:?} from client {}", client_id.to_bits());
+            continue;
+        }
+
+        match message {
+            TokenMessage::Spawn { image, position } => {
+                if !position.is_finite()
+                    || !world
+                        .resource::<SharedAssets<Image>>()
+                        .id_to_handle
+                        .contains_key(&image)
+                {
+                    info!("Rejected {message:?} from client {}", client_id.to_bits());
+                    continue;
+                }
+
+                let token = spawn_token(world, client_id, image, position);
+                world.send_event(RecordEdit {
+                    client: client_id,
+                    permission: Permission::ManageTable,
+                    undo: Edit::Despawn(vec![token]),
+                });
+            }
+            TokenMessage::Delete(token) => {
+                if world.get::<Token>(token).is_none() {
+                    continue;
+                }
+
+                world.send_event(ApplyEdit {
+                    client: client_id,
+                    permission: Permission::ManageTable,
+                    edit: Edit::Despawn(vec![token]),
+                });
+            }
+            TokenMessage::Duplicate(token) => {
+                if world.get::<Token>(token).is_none() {
+                    continue;
+                }
+
+                let mut bars = world.query::<(Entity, &ResourceBar)>();
+                let copied = std::iter::once(token)
+                    .chain(
+                        bars.iter(world)
+                            .filter(|(_, bar)| bar.token == token)
+                            .map(|(entity, _)| entity),
+                    )
+                    .collect::<Vec<_>>();
+
+                // Copy goes right next to the original, so it's offset by a whole footprint
+                let width = world
+                    .get::<TokenSize>(token)
+                    .copied()
+                    .unwrap_or_default()
+                    .width;
+                let scene = world.get::<InScene>(token).copied();
+                let cell_size = world
+                    .query::<(&GridSettings, Option<&InScene>)>()
+                    .iter(world)
+                    .find(|(_, grid_scene)| grid_scene.copied() == scene)
+                    .map_or(1.0, |(grid, _)| grid.cell_size);
+
+                let saved = save_entities(world, &copied);
+                let spawned = spawn_saved_entities(world, saved, |entity| entity);
+
+                if let Some(mut copy) = spawned
+                    .get(&token)
+                    .and_then(|copy| world.get_mut::<Token>(*copy))
+                {
+                    copy.position.x += width * cell_size;
+                }
+
+                world.send_event(RecordEdit {
+                    client: client_id,
+                    permission: Permission::ManageTable,
+                    undo: Edit::Despawn(spawned.into_values().collect()),
+                });
+            }
+        }
+    }
+}
+
+/// Plain token showing a shared image, on the scene the client is looking at
+pub(crate) fn spawn_token(
+    world: &mut World,
+    client_id: ClientId,
+    image: Uuid,
+    position: Vec2,
+) -> Entity {
+    // Tokens of the host are put on its scene by the scene plugin
+    let scene = world
+        .resource::<ClientScenes>()
+        .get(&client_id.to_bits())
+        .copied();
+
+    // Vision decides who gets to see it
+    let mut token = world.spawn((
+        Name::new("Token"),
+        Token {
+            position,
+            layer: DEFAULT_TOKEN_LAYER,
+        },
+        TokenName(String::from("Token")),
+        Sight::default(),
+        SharedAsset::<Image>::new(image),
+        server::Replicate {
+            target: ReplicationTarget {
+                target: NetworkTarget::None,
+            },
+            ..default()
+        },
+    ));
+
+    if let Some(scene) = scene {
+        token.insert(InScene(scene));
+    }
+
+    token.id()
+}
+
+/// Puts tokens back where they were if the player dragging them has left
+fn release_disconnected_drags(
+    mut disconnected: EventReader<DisconnectEvent>,
+    mut tokens: Query<&mut Token>,
+    mut dragged: ResMut<DraggedTokens>,
+) {
+    for disconnected in disconnected.read() {
+        let client = disconnected.client_id.to_bits();
+
+        dragged.retain(|entity, drag| {
+            if drag.client != client {
+                return true;
+            }
+
+            if let Ok(mut token) = tokens.get_mut(*entity) {
+                token.position = drag.start;
+            }
+
+            false
+        });
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Largest session or library read or written. Images are stored uncompressed, so this fits
+/// a few large maps, while a broken length in a file can't make a read allocate much
+pub const MAX_FILE_SIZE: u64 = 64 << 20;
+
+/// Header and size limit of a file on the host's disk. Files start with the magic and the version,
+/// the bincoded value comes after them
+pub struct FileFormat {
+    pub magic: [u8; 4],
+    /// Bumped whenever the stored type changes, files of other versions are refused instead of misread
+    pub version: u32,
+    /// What the file holds, for error messages
+    pub kind: &'static str,
+    /// Most bytes a read may take, so a broken length in a file can't allocate without bounds
+    pub max_size: u64,
+}
+
+impl FileFormat {
+    /// Fixed int encoding, same as `bincode::serialize_into`
+    fn options(&self) -> impl Options {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(self.max_size)
+    }
+
+    /// Writes to a temporary file first, so a crash halfway through doesn't eat the old file
+    pub fn write<T: Serialize>(&self, path: &Path, value: &T) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+
+        let temp_path = path.with_extension("tmp");
+        let file = File::create(&temp_path).map_err(|err| err.to_string())?;
+        let mut writer = BufWriter::new(file);
+
+        writer
+            .write_all(&self.magic)
+            .and_then(|_| writer.write_all(&self.version.to_le_bytes()))
+            .map_err(|err| err.to_string())?;
+        self.options()
+            .serialize_into(&mut writer, value)
+            .map_err(|err| err.to_string())?;
+        writer.flush().map_err(|err| err.to_string())?;
+        drop(writer);
+
+        std::fs::rename(&temp_path, path).map_err(|err| err.to_string())
+    }
+
+    pub fn read<T: DeserializeOwned>(&self, path: &Path) -> Result<T, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; 4];
+        let mut version = [0; 4];
+        reader
+            .read_exact(&mut magic)
+            .and_then(|_| reader.read_exact(&mut version))
+            .map_err(|err| err.to_string())?;
+
+        if magic != self.magic {
+            return Err(format!("not a {} file", self.kind));
+        }
+
+        let version = u32::from_le_bytes(version);
+        if version != self.version {
+            return Err(format!(
+                "{} was saved in version {version}, this build only reads version {}",
+                self.kind, self.version
+            ));
+        }
+
+        self.options()
+            .deserialize_from(reader)
+            .map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,374 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use crate::{networking::scenes::ClientScenes, prelude::*};
+use bevy::time::common_conditions::on_timer;
+use lightyear::prelude::{server::*, *};
+
+pub struct VisionPlugin;
+impl Plugin for VisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_token_visibility
+                .run_if(in_state(NetworkingState::Started))
+                .run_if(on_timer(Duration::from_millis(100))),
+        );
+    }
+}
+
+/// Rays cast around the circle in addition to the ones aimed at wall ends,
+/// so the edge of the sight radius stays round
+const CIRCLE_RAYS: usize = 64;
+
+/// Clients a token is currently replicated to
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq, Deref)]
+pub struct VisibleTo(pub Vec<u64>);
+
+/// Point where ray from `origin` in `direction` first crosses the segment, as distance along the ray
+pub fn ray_segment_intersection(
+    origin: Vec2,
+    direction: Vec2,
+    start: Vec2,
+    end: Vec2,
+) -> Option<f32> {
+    let segment = end - start;
+    let denominator = direction.perp_dot(segment);
+    if denominator.abs() <= f32::EPSILON {
+        return None;
+    }
+
+    let to_start = start - origin;
+    let distance = to_start.perp_dot(segment) / denominator;
+    let along_segment = to_start.perp_dot(direction) / denominator;
+
+    (distance >= 0.0 && (0.0..=1.0).contains(&along_segment)).then_some(distance)
+}
+
+pub fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let t =
+        ((point - start).dot(segment) / segment.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
+
+/// Whether segments `a` and `b` cross or touch
+pub fn segments_intersect(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> bool {
+    ray_segment_intersection(a.0, a.1 - a.0, b.0, b.1).is_some_and(|hit| hit <= 1.0)
+}
+
+/// Segments of walls that block sight
+pub fn sight_blockers<'a>(walls: impl IntoIterator<Item = &'a Wall>) -> Vec<(Vec2, Vec2)> {
+    walls
+        .into_iter()
+        .filter(|wall| wall.blocks_sight())
+        .map(|wall| (wall.start, wall.end))
+        .collect()
+}
+
+/// Whether `target` is within `radius` of `origin` and no wall is in the way
+pub fn is_visible(origin: Vec2, radius: f32, target: Vec2, walls: &[(Vec2, Vec2)]) -> bool {
+    let offset = target - origin;
+    let distance = offset.length();
+    if distance > radius {
+        return false;
+    }
+    if distance <= f32::EPSILON {
+        return true;
+    }
+
+    let direction = offset / distance;
+    !walls.iter().any(|(start, end)| {
+        ray_segment_intersection(origin, direction, *start, *end).is_some_and(|hit| hit < distance)
+    })
+}
+
+/// Area visible from `origin`, limited by `radius` and blocked by `walls`.
+/// Points are sorted by angle, so the polygon is star-shaped around the origin
+pub fn visibility_polygon(origin: Vec2, radius: f32, walls: &[(Vec2, Vec2)]) -> Vec<Vec2> {
+    if radius <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut angles = (0..CIRCLE_RAYS)
+        .map(|i| TAU * i as f32 / CIRCLE_RAYS as f32)
+        .collect::<Vec<_>>();
+
+    // Rays just past both sides of every wall end, to see around corners
+    for (start, end) in walls {
+        for corner in [start, end] {
+            let offset = *corner - origin;
+            if offset.length() > radius {
+                continue;
+            }
+
+            // Same range as the circle rays, or the polygon would cross itself once sorted
+            let angle = offset.y.atan2(offset.x);
+            angles
+                .extend([angle - 0.0001, angle, angle + 0.0001].map(|angle| angle.rem_euclid(TAU)));
+        }
+    }
+
+    angles.sort_by(f32::total_cmp);
+
+    angles
+        .into_iter()
+        .map(|angle| {
+            let direction = Vec2::from_angle(angle);
+            let distance = walls
+                .iter()
+                .filter_map(|(start, end)| {
+                    ray_segment_intersection(origin, direction, *start, *end)
+                })
+                .fold(radius, f32::min);
+
+            origin + direction * distance
+        })
+        .collect()
+}
+
+/// Even-odd test, works for concave and self intersecting polygons
+pub fn polygon_contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Only replicates tokens to clients on the token's scene that can see them.
+/// GM and owners always see a token, everyone does when token vision is off in that scene's fog
+fn update_token_visibility(
+    mut commands: Commands,
+    fogs: Query<(&FogOfWar, &InScene)>,
+    walls: Query<(&Wall, &InScene)>,
+    viewers: Query<(&Token, &Sight, &TokenOwners, &InScene)>,
+    mut tokens: Query<(
+        Entity,
+        &Token,
+        Option<&TokenOwners>,
+        &InScene,
+        Option<&VisibleTo>,
+        &mut ReplicationTarget,
+    )>,
+    client_scenes: Res<ClientScenes>,
+    roles: Res<PlayerRoles>,
+) {
+    let vision_scenes = fogs
+        .iter()
+        .filter(|(fog, _)| fog.vision)
+        .map(|(_, scene)| scene.0)
+        .collect::<Vec<_>>();
+
+    let mut blockers = HashMap::<Entity, Vec<&Wall>>::default();
+    for (wall, scene) in walls.iter() {
+        blockers.entry(scene.0).or_default().push(wall);
+    }
+    let blockers = blockers
+        .into_iter()
+        .map(|(scene, walls)| (scene, sight_blockers(walls)))
+        .collect::<HashMap<_, _>>();
+
+    for (entity, token, owners, scene, visible_to, mut target) in tokens.iter_mut() {
+        let walls = blockers
+            .get(&scene.0)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let vision = vision_scenes.contains(&scene.0);
+
+        let mut new_visible_to = client_scenes
+            .iter()
+            .filter(|(_, client_scene)| **client_scene == scene.0)
+            .map(|(client, _)| *client)
+            .filter(|client| {
+                let role = roles.client_role(ClientId::from_bits(*client));
+                let owns = |owners: &TokenOwners| owners.contains(client);
+
+                if !vision || role == Role::Gm || owners.is_some_and(owns) {
+                    return true;
+                }
+
+                // Spectators see what every player sees
+                viewers
+                    .iter()
+                    .filter(|(_, _, owners, viewer_scene)| {
+                        *viewer_scene == scene && (role == Role::Spectator || owns(owners))
+                    })
+                    .any(|(viewer, sight, _, _)| {
+                        is_visible(viewer.position, sight.radius, token.position, walls)
+                    })
+            })
+            .collect::<Vec<_>>();
+        new_visible_to.sort_unstable();
+
+        let new_visible_to = VisibleTo(new_visible_to);
+        if visible_to == Some(&new_visible_to) {
+            continue;
+        }
+
+        target.target = NetworkTarget::Only(
+            new_visible_to
+                .0
+                .iter()
+                .map(|client| ClientId::from_bits(*client))
+                .collect(),
+        );
+        commands.entity(entity).insert(new_visible_to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wall under the origin, facing it
+    const WALL_BELOW: (Vec2, Vec2) = (Vec2::new(-1.0, -2.0), Vec2::new(1.0, -2.0));
+
+    fn unit_square() -> Vec<Vec2> {
+        vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn ray_hits_segment_in_front() {
+        let hit = ray_segment_intersection(
+            Vec2::ZERO,
+            Vec2::X,
+            Vec2::new(2.0, -1.0),
+            Vec2::new(2.0, 1.0),
+        );
+        assert_eq!(hit, Some(2.0));
+    }
+
+    #[test]
+    fn ray_misses_segment_behind_or_beside() {
+        let (start, end) = (Vec2::new(2.0, -1.0), Vec2::new(2.0, 1.0));
+        assert_eq!(
+            ray_segment_intersection(Vec2::ZERO, -Vec2::X, start, end),
+            None
+        );
+        assert_eq!(
+            ray_segment_intersection(Vec2::ZERO, Vec2::Y, start, end),
+            None
+        );
+        // Parallel
+        assert_eq!(
+            ray_segment_intersection(Vec2::ZERO, Vec2::Y, Vec2::X, Vec2::new(1.0, 2.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn crossing_segments_intersect() {
+        let a = (Vec2::new(-1.0, 0.0), Vec2::new(1.0, 0.0));
+        let b = (Vec2::new(0.0, -1.0), Vec2::new(0.0, 1.0));
+        assert!(segments_intersect(a, b));
+        assert!(segments_intersect(b, a));
+    }
+
+    #[test]
+    fn short_segment_stops_before_wall() {
+        let wall = (Vec2::new(2.0, -1.0), Vec2::new(2.0, 1.0));
+        assert!(!segments_intersect((Vec2::ZERO, Vec2::new(1.0, 0.0)), wall));
+        assert!(segments_intersect((Vec2::ZERO, Vec2::new(3.0, 0.0)), wall));
+    }
+
+    #[test]
+    fn visible_within_radius_without_walls() {
+        assert!(is_visible(Vec2::ZERO, 5.0, Vec2::new(3.0, 3.0), &[]));
+        assert!(is_visible(Vec2::ZERO, 5.0, Vec2::ZERO, &[]));
+        assert!(!is_visible(Vec2::ZERO, 5.0, Vec2::new(4.0, 4.0), &[]));
+    }
+
+    #[test]
+    fn walls_block_visibility() {
+        assert!(!is_visible(
+            Vec2::ZERO,
+            5.0,
+            Vec2::new(0.0, -3.0),
+            &[WALL_BELOW]
+        ));
+        assert!(is_visible(
+            Vec2::ZERO,
+            5.0,
+            Vec2::new(0.0, -1.0),
+            &[WALL_BELOW]
+        ));
+        assert!(is_visible(
+            Vec2::ZERO,
+            5.0,
+            Vec2::new(0.0, 3.0),
+            &[WALL_BELOW]
+        ));
+    }
+
+    #[test]
+    fn polygon_contains_points_inside_only() {
+        let square = unit_square();
+        assert!(polygon_contains(&square, Vec2::new(0.5, 0.5)));
+        assert!(!polygon_contains(&square, Vec2::new(1.5, 0.5)));
+        assert!(!polygon_contains(&square, Vec2::new(0.5, -0.5)));
+    }
+
+    #[test]
+    fn polygon_contains_handles_concave_polygons() {
+        // U shape opening upwards
+        let polygon = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 3.0),
+            Vec2::new(2.0, 3.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 3.0),
+            Vec2::new(0.0, 3.0),
+        ];
+        assert!(polygon_contains(&polygon, Vec2::new(0.5, 2.0)));
+        assert!(polygon_contains(&polygon, Vec2::new(1.5, 0.5)));
+        assert!(!polygon_contains(&polygon, Vec2::new(1.5, 2.0)));
+    }
+
+    #[test]
+    fn visibility_polygon_without_walls_is_a_circle() {
+        let polygon = visibility_polygon(Vec2::ONE, 2.0, &[]);
+        assert_eq!(polygon.len(), CIRCLE_RAYS);
+        for point in polygon {
+            assert!((point.distance(Vec2::ONE) - 2.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn visibility_polygon_is_empty_without_radius() {
+        assert!(visibility_polygon(Vec2::ZERO, 0.0, &[WALL_BELOW]).is_empty());
+    }
+
+    #[test]
+    fn visibility_polygon_goes_around_once() {
+        let polygon = visibility_polygon(Vec2::ZERO, 5.0, &[WALL_BELOW]);
+        let angles = polygon
+            .iter()
+            .map(|point| point.y.atan2(point.x).rem_euclid(TAU))
+            .collect::<Vec<_>>();
+
+        assert!(angles.windows(2).all(|pair| pair[0] <= pair[1] + 1e-4));
+    }
+
+    #[test]
+    fn visibility_polygon_hides_area_behind_wall_below() {
+        let polygon = visibility_polygon(Vec2::ZERO, 5.0, &[WALL_BELOW]);
+
+        assert!(polygon_contains(&polygon, Vec2::new(0.0, -1.0)));
+        assert!(polygon_contains(&polygon, Vec2::new(0.0, 3.0)));
+        assert!(polygon_contains(&polygon, Vec2::new(3.0, -3.0)));
+        assert!(!polygon_contains(&polygon, Vec2::new(0.0, -3.0)));
+        assert!(!polygon_contains(&polygon, Vec2::new(0.5, -4.0)));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    networking::{scenes::client_scene, vision::distance_to_segment},
+    prelude::*,
+};
+use lightyear::prelude::{server::*, *};
+
+pub struct WallPlugin;
+impl Plugin for WallPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            handle_wall_requests.run_if(in_state(NetworkingState::Started)),
+        );
+    }
+}
+
+/// How close, in cells, a token of the player has to be to open or close a door
+const DOOR_REACH: f32 = 1.5;
+
+fn handle_wall_requests(
+    mut commands: Commands,
+    mut requests: EventReader<MessageEvent<WallMessage>>,
+    mut walls: Query<(&mut Wall, Option<&InScene>)>,
+    tokens: Query<(&Token, &TokenOwners, Option<&InScene>)>,
+    grids: Query<(&GridSettings, Option<&InScene>)>,
+    scenes: Query<(Entity, &TableScene)>,
+    roles: Res<PlayerRoles>,
+) {
+    for request in requests.read() {
+        let client_id = request.context;
+        let role = roles.client_role(client_id);
+
+        match request.message {
+            WallMessage::Place(wall) => {
+                let valid =
+                    wall.start.is_finite() && wall.end.is_finite() && wall.start != wall.end;
+
+                if !role.allows(Permission::ManageTable) || !valid {
+                    info!(
+                        "Rejected {:?} from client {}",
+                        request.message,
+                        client_id.to_bits()
+                    );
+                    continue;
+                }
+
+                let mut entity = commands.spawn((
+                    Name::new("Wall"),
+                    wall,
+                    server::Replicate {
+                        target: ReplicationTarget {
+                            target: NetworkTarget::None,
+                        },
+                        ..default()
+                    },
+                ));
+
+                // Walls go where the GM placing them is looking, not where the host is
+                if let Some(scene) = client_scene(scenes.iter(), client_id.to_bits()) {
+                    entity.insert(InScene(scene));
+                }
+            }
+            WallMessage::Remove(entity) => {
+                if !role.allows(Permission::ManageTable) || walls.get(entity).is_err() {
+                    info!(
+                        "Rejected {:?} from client {}",
+                        request.message,
+                        client_id.to_bits()
+                    );
+                    continue;
+                }
+
+                commands.entity(entity).despawn();
+            }
+            WallMessage::SetDoor(entity, state) => {
+                let Ok((mut wall, wall_scene)) = walls.get_mut(entity) else {
+                    continue;
+                };
+
+                let WallKind::Door(current) = wall.kind else {
+                    continue;
+                };
+
+                // Players may only open and close unlocked doors on their scene,
+                // and only while one of their tokens is next to it
+                let client = client_id.to_bits();
+                let wall_scene = wall_scene.map(|scene| scene.0);
+                let on_scene = client_scene(scenes.iter(), client) == wall_scene;
+
+                let cell_size = grids
+                    .iter()
+                    .find(|(_, grid_scene)| grid_scene.map(|scene| scene.0) == wall_scene)
+                    .map_or(1.0, |(grid, _)| grid.cell_size);
+                let within_reach = tokens.iter().any(|(token, owners, token_scene)| {
+                    token_scene.map(|scene| scene.0) == wall_scene
+                        && owners.contains(&client)
+                        && distance_to_segment(token.position, wall.start, wall.end)
+                            <= DOOR_REACH * cell_size
+                });
+
+                let allowed = role.allows(Permission::ManageTable)
+                    || (role.allows(Permission::MoveTokens)
+                        && on_scene
+                        && within_reach
+                        && current != DoorState::Locked
+                        && state != DoorState::Locked);
+
+                if !allowed {
+                    info!(
+                        "Rejected {:?} from client {}",
+                        request.message,
+                        client_id.to_bits()
+                    );
+                    continue;
+                }
+
+                wall.kind = WallKind::Door(state);
+            }
+        }
+    }
+}
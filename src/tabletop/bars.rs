@@ -0,0 +1,104 @@
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+
+use crate::{
+    prelude::*,
+    tabletop::{scenes::OffScene, TopdownCamera},
+};
+
+pub struct BarPlugin;
+impl Plugin for BarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, draw_bars);
+    }
+}
+
+/// Height of a bar in pixels, at most
+const BAR_HEIGHT: f32 = 7.0;
+
+/// Bars are drawn over the bottom of their token, first bar at the very bottom.
+/// Hovering a bar shows its value, `+` and `-` change it by one
+fn draw_bars(
+    bars: Query<(Entity, &ResourceBar)>,
+    tokens: Query<&GlobalTransform, (With<Token>, Without<OffScene>)>,
+    camera: Query<(&Camera, &GlobalTransform), With<TopdownCamera>>,
+    egui: Query<&EguiContext>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    let Ok((camera, camera_transform)) = camera.get_single() else {
+        return;
+    };
+
+    let ctx = egui.single().get();
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let hover = ctx.pointer_hover_pos();
+    let typing = ctx.wants_keyboard_input();
+
+    let mut by_token = HashMap::<Entity, Vec<(Entity, &ResourceBar)>>::default();
+    for (entity, bar) in bars.iter() {
+        by_token.entry(bar.token).or_default().push((entity, bar));
+    }
+
+    for (token, mut bars) in by_token {
+        let Ok(transform) = tokens.get(token) else {
+            continue;
+        };
+
+        let (scale, _, translation) = transform.to_scale_rotation_translation();
+        let corners = [translation - scale / 2.0, translation + scale / 2.0]
+            .map(|corner| camera.world_to_viewport(camera_transform, corner));
+
+        let [Some(min), Some(max)] = corners else {
+            continue;
+        };
+
+        let rect = egui::Rect::from_two_pos(egui::pos2(min.x, min.y), egui::pos2(max.x, max.y));
+        let height = (rect.height() / 10.0).min(BAR_HEIGHT);
+
+        // Entities are created in order, which keeps bars from jumping around
+        bars.sort_by_key(|(entity, _)| *entity);
+
+        for (i, (entity, bar)) in bars.into_iter().enumerate() {
+            let bottom = rect.bottom() - i as f32 * (height + 1.0);
+            let bar_rect = egui::Rect::from_min_max(
+                egui::pos2(rect.left(), bottom - height),
+                egui::pos2(rect.right(), bottom),
+            );
+            let [r, g, b] = bar.color;
+
+            painter.rect_filled(bar_rect, 1.0, egui::Color32::from_black_alpha(180));
+            painter.rect_filled(
+                bar_rect.with_max_x(bar_rect.left() + bar_rect.width() * bar.fraction()),
+                1.0,
+                egui::Color32::from_rgb(r, g, b),
+            );
+
+            if !hover.is_some_and(|hover| bar_rect.contains(hover)) {
+                continue;
+            }
+
+            painter.text(
+                bar_rect.center_top() - egui::vec2(0.0, 2.0),
+                egui::Align2::CENTER_BOTTOM,
+                format!("{} {}/{}", bar.label, bar.value, bar.max),
+                egui::FontId::proportional(14.0),
+                egui::Color32::WHITE,
+            );
+
+            if typing {
+                continue;
+            }
+
+            let delta = if key_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+                1
+            } else if key_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+                -1
+            } else {
+                continue;
+            };
+
+            _ = connection.send_message::<UnorderedReliable, _>(&BarMessage::Adjust(entity, delta));
+        }
+    }
+}
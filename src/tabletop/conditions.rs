@@ -0,0 +1,100 @@
+use bevy_egui::EguiContext;
+
+use crate::{
+    prelude::*,
+    tabletop::{scenes::OffScene, TopdownCamera},
+};
+
+pub struct ConditionPlugin;
+impl Plugin for ConditionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, draw_conditions);
+    }
+}
+
+/// Icons are spread around the border of a token in this many slots, more icons squeeze closer
+const CONDITION_SLOTS: usize = 8;
+
+/// Largest icon radius in pixels, icons shrink with the token when zooming out
+const MAX_ICON_RADIUS: f32 = 11.0;
+
+/// Point on the border of a rectangle, `t` going from 0 to 1 clockwise from the top left corner
+fn point_on_border(rect: egui::Rect, t: f32) -> egui::Pos2 {
+    let (width, height) = (rect.width(), rect.height());
+    let mut distance = t.rem_euclid(1.0) * 2.0 * (width + height);
+
+    for (start, direction, length) in [
+        (rect.left_top(), egui::vec2(1.0, 0.0), width),
+        (rect.right_top(), egui::vec2(0.0, 1.0), height),
+        (rect.right_bottom(), egui::vec2(-1.0, 0.0), width),
+        (rect.left_bottom(), egui::vec2(0.0, -1.0), height),
+    ] {
+        if distance <= length {
+            return start + direction * distance;
+        }
+        distance -= length;
+    }
+
+    rect.left_top()
+}
+
+fn draw_conditions(
+    tokens: Query<(&GlobalTransform, &TokenConditions), (With<Token>, Without<OffScene>)>,
+    camera: Query<(&Camera, &GlobalTransform), With<TopdownCamera>>,
+    egui: Query<&EguiContext>,
+) {
+    let Ok((camera, camera_transform)) = camera.get_single() else {
+        return;
+    };
+
+    let ctx = egui.single().get();
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let hover = ctx.pointer_hover_pos();
+
+    for (transform, conditions) in tokens.iter() {
+        if conditions.is_empty() {
+            continue;
+        }
+
+        let (scale, _, translation) = transform.to_scale_rotation_translation();
+        let corners = [translation - scale / 2.0, translation + scale / 2.0]
+            .map(|corner| camera.world_to_viewport(camera_transform, corner));
+
+        let [Some(min), Some(max)] = corners else {
+            continue;
+        };
+
+        let rect = egui::Rect::from_two_pos(egui::pos2(min.x, min.y), egui::pos2(max.x, max.y));
+        let radius = (rect.width().min(rect.height()) / 8.0).min(MAX_ICON_RADIUS);
+        let slots = conditions.len().max(CONDITION_SLOTS);
+
+        for (i, condition) in conditions.iter().enumerate() {
+            let center = point_on_border(rect, i as f32 / slots as f32);
+            let [r, g, b] = condition.color;
+
+            painter.circle(
+                center,
+                radius,
+                egui::Color32::from_rgb(r, g, b),
+                egui::Stroke::new(1.0, egui::Color32::BLACK),
+            );
+            painter.text(
+                center,
+                egui::Align2::CENTER_CENTER,
+                &condition.icon,
+                egui::FontId::proportional(radius * 1.3),
+                egui::Color32::WHITE,
+            );
+
+            if hover.is_some_and(|hover| hover.distance(center) <= radius) {
+                painter.text(
+                    center + egui::vec2(0.0, -radius - 2.0),
+                    egui::Align2::CENTER_BOTTOM,
+                    &condition.label,
+                    egui::FontId::proportional(14.0),
+                    egui::Color32::WHITE,
+                );
+            }
+        }
+    }
+}
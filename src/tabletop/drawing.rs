@@ -0,0 +1,322 @@
+use bevy::render::{
+    mesh::{Indices, PrimitiveTopology},
+    render_asset::RenderAssetUsages,
+};
+use lightyear::prelude::*;
+
+use crate::{
+    input::{CursorPosition, OverUI},
+    networking::{
+        client::{local_role, ClientId},
+        drawings::MAX_STROKE_WIDTH,
+        vision::distance_to_segment,
+    },
+    prelude::*,
+    tabletop::{
+        scenes::OffScene,
+        tools::{tool_active, Tool},
+    },
+};
+
+pub struct DrawingPlugin;
+impl Plugin for DrawingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DrawTool>().add_systems(
+            Update,
+            (
+                draw_strokes.run_if(tool_active(Tool::Draw)),
+                (init_strokes, update_stroke_colors).chain(),
+                draw_preview,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Height drawings are rendered at, above the tokens
+const DRAWING_LAYER: f32 = 20.0;
+
+/// Eraser removes strokes this close to the cursor
+const ERASER_RADIUS: f32 = 0.3;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DrawMode {
+    #[default]
+    Pen,
+    Highlighter,
+    Eraser,
+}
+
+impl DrawMode {
+    pub const ALL: [DrawMode; 3] = [DrawMode::Pen, DrawMode::Highlighter, DrawMode::Eraser];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DrawMode::Pen => "Pen",
+            DrawMode::Highlighter => "Highlighter",
+            DrawMode::Eraser => "Eraser",
+        }
+    }
+}
+
+/// Settings and unfinished stroke of the drawing tool
+#[derive(Resource)]
+pub struct DrawTool {
+    pub mode: DrawMode,
+    pub pen_width: f32,
+    pub highlighter_width: f32,
+    points: Vec<Vec2>,
+    /// Strokes already sent for removal during this eraser drag
+    erased: Vec<Entity>,
+}
+
+impl Default for DrawTool {
+    fn default() -> Self {
+        Self {
+            mode: DrawMode::default(),
+            pen_width: 0.05,
+            highlighter_width: 0.4,
+            points: Vec::new(),
+            erased: Vec::new(),
+        }
+    }
+}
+
+impl DrawTool {
+    fn stroke(&self) -> Option<Stroke> {
+        let (kind, width) = match self.mode {
+            DrawMode::Pen => (StrokeKind::Pen, self.pen_width),
+            DrawMode::Highlighter => (StrokeKind::Highlighter, self.highlighter_width),
+            DrawMode::Eraser => return None,
+        };
+
+        Some(Stroke {
+            kind,
+            points: self.points.clone(),
+            width: width.clamp(0.01, MAX_STROKE_WIDTH),
+        })
+    }
+}
+
+impl Stroke {
+    pub fn distance(&self, point: Vec2) -> f32 {
+        match self.points.as_slice() {
+            [] => f32::INFINITY,
+            [single] => single.distance(point),
+            points => points
+                .windows(2)
+                .map(|segment| distance_to_segment(point, segment[0], segment[1]))
+                .fold(f32::INFINITY, f32::min),
+        }
+    }
+
+    fn alpha(&self) -> f32 {
+        match self.kind {
+            StrokeKind::Pen => 1.0,
+            StrokeKind::Highlighter => 0.35,
+        }
+    }
+
+    /// Highlighter goes under the pen
+    fn layer(&self) -> f32 {
+        match self.kind {
+            StrokeKind::Pen => DRAWING_LAYER + 0.1,
+            StrokeKind::Highlighter => DRAWING_LAYER,
+        }
+    }
+}
+
+/// Ribbon of triangles following the points, with square caps on both ends
+pub fn stroke_mesh(points: &[Vec2], width: f32) -> Mesh {
+    let mut points = points.to_vec();
+    if points.len() == 1 {
+        points.push(points[0] + Vec2::X * 0.001);
+    }
+
+    let half_width = width / 2.0;
+    let positions = points
+        .iter()
+        .enumerate()
+        .flat_map(|(i, point)| {
+            let previous = points[i.saturating_sub(1)];
+            let next = points[(i + 1).min(points.len() - 1)];
+            let direction = (next - previous).normalize_or_zero();
+            let normal = direction.perp() * half_width;
+
+            // Extend the ends, so dots and short lines are still visible
+            let cap = if i == 0 {
+                -direction * half_width
+            } else if i == points.len() - 1 {
+                direction * half_width
+            } else {
+                Vec2::ZERO
+            };
+
+            [point + normal + cap, point - normal + cap].map(|corner| [corner.x, corner.y, 0.0])
+        })
+        .collect::<Vec<_>>();
+
+    let indices = (0..points.len().saturating_sub(1) as u32)
+        .flat_map(|i| {
+            let j = i * 2;
+            [j, j + 1, j + 2, j + 1, j + 3, j + 2]
+        })
+        .collect();
+
+    let count = positions.len();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0f32, 0.0, 1.0]; count])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0f32, 0.0]; count])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+fn stroke_color(stroke: &Stroke, owner: Option<&Owner>, player_data: &PlayerData) -> Color {
+    let color = owner
+        .and_then(|owner| player_data.get(&owner.0))
+        .map(|player| player.color)
+        .unwrap_or([255; 3]);
+    Color::rgba_u8(color[0], color[1], color[2], (stroke.alpha() * 255.0) as u8)
+}
+
+/// Pen and highlighter draw while the left button is held, eraser removes strokes it touches
+fn draw_strokes(
+    mut tool: ResMut<DrawTool>,
+    strokes: Query<(Entity, &Stroke, &Owner), Without<OffScene>>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    client_id: Res<ClientId>,
+    roles: Res<PlayerRoles>,
+    server_state: Res<State<server::NetworkingState>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    let cursor = cursor_pos.world_position;
+
+    if mouse_input.just_pressed(MouseButton::Left) && !**over_ui {
+        tool.points = vec![cursor];
+    }
+
+    if tool.points.is_empty() {
+        return;
+    }
+
+    if !mouse_input.pressed(MouseButton::Left) {
+        if let Some(stroke) = tool.stroke() {
+            _ = connection.send_message::<UnorderedReliable, _>(&DrawMessage::Stroke(stroke));
+        }
+        tool.points.clear();
+        tool.erased.clear();
+        return;
+    }
+
+    if tool.mode != DrawMode::Eraser {
+        if tool
+            .points
+            .last()
+            .is_some_and(|last| last.distance(cursor) > 0.02)
+        {
+            tool.points.push(cursor);
+        }
+        return;
+    }
+
+    let is_gm = local_role(&client_id, &roles, &server_state) == Role::Gm;
+    let erased = strokes
+        .iter()
+        .filter(|(entity, stroke, owner)| {
+            (is_gm || owner.0 == client_id.0)
+                && !tool.erased.contains(entity)
+                && stroke.distance(cursor) <= ERASER_RADIUS + stroke.width / 2.0
+        })
+        .map(|(entity, _, _)| entity)
+        .collect::<Vec<_>>();
+
+    if !erased.is_empty() {
+        _ = connection.send_message::<UnorderedReliable, _>(&DrawMessage::Remove(erased.clone()));
+        tool.erased.extend(erased);
+    }
+}
+
+fn init_strokes(
+    mut commands: Commands,
+    strokes: Query<(Entity, &Stroke, Option<&Owner>), Added<Stroke>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    player_data: Res<PlayerData>,
+) {
+    for (entity, stroke, owner) in strokes.iter() {
+        let material = materials.add(StandardMaterial {
+            unlit: true,
+            base_color: stroke_color(stroke, owner, &player_data),
+            alpha_mode: AlphaMode::Blend,
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        });
+
+        commands.entity(entity).insert((
+            PbrBundle {
+                mesh: meshes.add(stroke_mesh(&stroke.points, stroke.width)),
+                material,
+                transform: Transform::from_xyz(0.0, 0.0, stroke.layer()),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+/// Strokes follow their author's color
+fn update_stroke_colors(
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    strokes: Query<(&Stroke, Option<&Owner>, &Handle<StandardMaterial>)>,
+    player_data: Res<PlayerData>,
+) {
+    if !player_data.is_changed() {
+        return;
+    }
+
+    for (stroke, owner, material) in strokes.iter() {
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = stroke_color(stroke, owner, &player_data);
+        }
+    }
+}
+
+fn draw_preview(
+    mut gizmos: Gizmos,
+    mut tool: ResMut<DrawTool>,
+    active_tool: Res<Tool>,
+    cursor_pos: Res<CursorPosition>,
+    player: Res<Player>,
+) {
+    if *active_tool != Tool::Draw {
+        if !tool.points.is_empty() {
+            tool.points.clear();
+        }
+        return;
+    }
+
+    let color = Color::rgb_u8(player.color[0], player.color[1], player.color[2]);
+
+    if tool.mode == DrawMode::Eraser {
+        gizmos.circle(
+            cursor_pos.world_position.extend(DRAWING_LAYER + 0.2),
+            Direction3d::Z,
+            ERASER_RADIUS,
+            color,
+        );
+        return;
+    }
+
+    gizmos.linestrip(
+        tool.points
+            .iter()
+            .map(|point| point.extend(DRAWING_LAYER + 0.2)),
+        color,
+    );
+}
@@ -0,0 +1,589 @@
+use bevy::render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+
+use crate::{
+    input::{CursorPosition, OverUI},
+    networking::{
+        client::{local_player_is_gm, local_role, ClientId},
+        vision::{distance_to_segment, polygon_contains, sight_blockers, visibility_polygon},
+    },
+    prelude::*,
+    tabletop::{
+        scenes::OffScene,
+        tools::{tool_active, Tool},
+    },
+};
+
+pub struct FogPlugin;
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FogTool>().add_systems(
+            Update,
+            (
+                paint_fog.run_if(tool_active(Tool::Fog).and_then(local_player_is_gm)),
+                (init_fog, update_fog).chain(),
+                draw_fog_tool,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Height fog is drawn at, above the tokens
+const FOG_LAYER: f32 = 30.0;
+
+/// Fog texture resolution, in pixels per world unit
+const FOG_RESOLUTION: f32 = 8.0;
+
+/// How dark the fog looks to the GM, so they can still see what is under it
+const GM_FOG_ALPHA: u8 = 140;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FogMode {
+    #[default]
+    RevealBrush,
+    HideBrush,
+    RevealPolygon,
+    HidePolygon,
+}
+
+impl FogMode {
+    pub const ALL: [FogMode; 4] = [
+        FogMode::RevealBrush,
+        FogMode::HideBrush,
+        FogMode::RevealPolygon,
+        FogMode::HidePolygon,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FogMode::RevealBrush => "Reveal brush",
+            FogMode::HideBrush => "Hide brush",
+            FogMode::RevealPolygon => "Reveal polygon",
+            FogMode::HidePolygon => "Hide polygon",
+        }
+    }
+
+    fn op(self, shape: FogShape) -> FogOp {
+        match self {
+            FogMode::RevealBrush | FogMode::RevealPolygon => FogOp::Reveal(shape),
+            FogMode::HideBrush | FogMode::HidePolygon => FogOp::Hide(shape),
+        }
+    }
+}
+
+/// Settings and unfinished shape of the fog tool
+#[derive(Resource)]
+pub struct FogTool {
+    pub mode: FogMode,
+    pub brush_radius: f32,
+    points: Vec<Vec2>,
+}
+
+impl Default for FogTool {
+    fn default() -> Self {
+        Self {
+            mode: FogMode::default(),
+            brush_radius: 1.0,
+            points: Vec::new(),
+        }
+    }
+}
+
+/// Texture the fog is rendered into
+#[derive(Component)]
+struct FogTexture(Handle<Image>);
+
+/// Last rasterized fog, so only ops added since then have to be drawn
+#[derive(Component, Default)]
+struct FogMaskCache {
+    base: FogMask,
+    ops: Vec<FogOp>,
+    mask: Vec<u8>,
+}
+
+impl FogMaskCache {
+    fn update(&mut self, fog: &FogOfWar) -> &[u8] {
+        let len = fog.texture_size().element_product() as usize;
+
+        // Fog was reset or compacted, start over
+        if self.base != fog.base || !fog.ops.starts_with(&self.ops) || self.mask.len() != len {
+            self.base = fog.base.clone();
+            self.ops.clear();
+            self.mask = fog.base.decode(len);
+        }
+
+        fog.rasterize_ops(&mut self.mask, &fog.ops[self.ops.len()..]);
+        self.ops = fog.ops.clone();
+        &self.mask
+    }
+}
+
+impl FogMask {
+    /// Packs a mask made by [`FogOfWar::rasterize`]
+    pub fn encode(mask: &[u8]) -> Self {
+        let mut runs = Vec::new();
+        let mut revealed = false;
+        let mut run = 0;
+
+        for pixel in mask {
+            if (*pixel != 0) != revealed {
+                runs.push(run);
+                run = 0;
+                revealed = !revealed;
+            }
+            run += 1;
+        }
+        runs.push(run);
+
+        FogMask(runs)
+    }
+
+    /// Unpacks `len` pixels. Anything the runs don't cover stays hidden
+    pub fn decode(&self, len: usize) -> Vec<u8> {
+        let mut mask = Vec::with_capacity(len);
+
+        for (i, run) in self.0.iter().enumerate() {
+            let value = if i % 2 == 1 { 255 } else { 0 };
+            let run = (*run as usize).min(len - mask.len());
+            mask.extend(std::iter::repeat(value).take(run));
+        }
+        mask.resize(len, 0);
+
+        mask
+    }
+
+    pub fn is_revealed(&self, index: usize) -> bool {
+        let mut end = 0;
+        for (i, run) in self.0.iter().enumerate() {
+            end += *run as usize;
+            if index < end {
+                return i % 2 == 1;
+            }
+        }
+        false
+    }
+}
+
+impl FogShape {
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            FogShape::Brush { points, radius } => {
+                if points.len() == 1 {
+                    return points[0].distance_squared(point) <= radius * radius;
+                }
+                points
+                    .windows(2)
+                    .any(|segment| distance_to_segment(point, segment[0], segment[1]) <= *radius)
+            }
+            FogShape::Polygon(points) => polygon_contains(points, point),
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        match self {
+            FogShape::Brush { points, radius } => points_bounds(points).inset(*radius),
+            FogShape::Polygon(points) => points_bounds(points),
+        }
+    }
+}
+
+fn points_bounds(points: &[Vec2]) -> Rect {
+    points.iter().fold(
+        // Not `Rect::new`, which would swap the corners
+        Rect {
+            min: Vec2::MAX,
+            max: Vec2::MIN,
+        },
+        |rect, point| Rect {
+            min: rect.min.min(*point),
+            max: rect.max.max(*point),
+        },
+    )
+}
+
+impl FogOfWar {
+    pub fn rect(&self) -> Rect {
+        Rect::from_corners(self.min, self.max)
+    }
+
+    /// Whether players can see given world position
+    pub fn is_revealed(&self, point: Vec2) -> bool {
+        if !self.enabled || !self.rect().contains(point) {
+            return true;
+        }
+
+        let mut revealed = self
+            .pixel_index(point)
+            .is_some_and(|index| self.base.is_revealed(index));
+        for op in &self.ops {
+            match op {
+                FogOp::Reveal(shape) if shape.contains(point) => revealed = true,
+                FogOp::Hide(shape) if shape.contains(point) => revealed = false,
+                _ => (),
+            }
+        }
+        revealed
+    }
+
+    /// Size of the fog texture in pixels
+    pub fn texture_size(&self) -> UVec2 {
+        (self.rect().size() * FOG_RESOLUTION)
+            .ceil()
+            .as_uvec2()
+            .max(UVec2::ONE)
+    }
+
+    /// Pixel of the fog texture that covers given world position
+    fn pixel_index(&self, point: Vec2) -> Option<usize> {
+        let size = self.texture_size();
+        let rect = self.rect();
+        let x = ((point.x - rect.min.x) * FOG_RESOLUTION).floor();
+        let y = ((rect.max.y - point.y) * FOG_RESOLUTION).floor();
+
+        (x >= 0.0 && y >= 0.0 && x < size.x as f32 && y < size.y as f32)
+            .then(|| y as usize * size.x as usize + x as usize)
+    }
+
+    /// Mask with one byte per pixel, `255` where the map is revealed. First row is the top of the map
+    pub fn rasterize(&self) -> Vec<u8> {
+        let mut mask = self
+            .base
+            .decode(self.texture_size().element_product() as usize);
+        self.rasterize_ops(&mut mask, &self.ops);
+        mask
+    }
+
+    /// Draws the ops on top of a mask made by [`FogOfWar::rasterize`]
+    pub fn rasterize_ops(&self, mask: &mut [u8], ops: &[FogOp]) {
+        for op in ops {
+            let (shape, value) = match op {
+                FogOp::Reveal(shape) => (shape, 255),
+                FogOp::Hide(shape) => (shape, 0),
+            };
+            self.fill(mask, shape.bounds(), value, |point| shape.contains(point));
+        }
+    }
+
+    /// Collapses the ops into the base mask
+    pub fn compact(&mut self) {
+        self.base = FogMask::encode(&self.rasterize());
+        self.ops.clear();
+    }
+
+    /// Mask of the area covered by any of the visibility polygons, same layout as [`FogOfWar::rasterize`]
+    pub fn rasterize_vision(&self, polygons: &[Vec<Vec2>]) -> Vec<u8> {
+        let mut mask = vec![0u8; self.texture_size().element_product() as usize];
+
+        for polygon in polygons {
+            self.fill(&mut mask, points_bounds(polygon), 255, |point| {
+                polygon_contains(polygon, point)
+            });
+        }
+
+        mask
+    }
+
+    /// Sets pixels inside of `bounds` that pass `contains` to `value`
+    fn fill(&self, mask: &mut [u8], bounds: Rect, value: u8, contains: impl Fn(Vec2) -> bool) {
+        let size = self.texture_size();
+        let rect = self.rect();
+
+        // Only walk over the pixels the shape could cover
+        let bounds = bounds.intersect(rect);
+        if bounds.is_empty() {
+            return;
+        }
+
+        let min_x = ((bounds.min.x - rect.min.x) * FOG_RESOLUTION)
+            .floor()
+            .max(0.0) as u32;
+        let max_x = (((bounds.max.x - rect.min.x) * FOG_RESOLUTION).ceil() as u32).min(size.x);
+        let min_y = ((rect.max.y - bounds.max.y) * FOG_RESOLUTION)
+            .floor()
+            .max(0.0) as u32;
+        let max_y = (((rect.max.y - bounds.min.y) * FOG_RESOLUTION).ceil() as u32).min(size.y);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let point = Vec2::new(
+                    rect.min.x + (x as f32 + 0.5) / FOG_RESOLUTION,
+                    rect.max.y - (y as f32 + 0.5) / FOG_RESOLUTION,
+                );
+                if contains(point) {
+                    mask[(y * size.x + x) as usize] = value;
+                }
+            }
+        }
+    }
+}
+
+/// What the local player's tokens can see, when token vision limits it
+type Vision = Vec<Vec<Vec2>>;
+
+fn fog_image(
+    fog: &FogOfWar,
+    darkness: u8,
+    fog_mask: Option<&[u8]>,
+    vision: Option<&Vision>,
+) -> Image {
+    let size = fog.texture_size();
+    let vision_mask = vision.map(|polygons| fog.rasterize_vision(polygons));
+
+    let data = (0..size.element_product() as usize)
+        .flat_map(|i| {
+            let hidden = fog_mask.is_some_and(|mask| mask[i] == 0)
+                || vision_mask.as_ref().is_some_and(|mask| mask[i] == 0);
+            [0, 0, 0, if hidden { darkness } else { 0 }]
+        })
+        .collect();
+
+    Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            ..default()
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+fn fog_transform(fog: &FogOfWar) -> Transform {
+    let rect = fog.rect();
+    Transform::from_translation(rect.center().extend(FOG_LAYER)).with_scale(rect.size().extend(1.0))
+}
+
+fn fog_visibility(fog: &FogOfWar, vision: Option<&Vision>) -> Visibility {
+    if fog.enabled || vision.is_some() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+fn init_fog(
+    mut commands: Commands,
+    fogs: Query<(Entity, &FogOfWar), Added<FogOfWar>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (entity, fog) in fogs.iter() {
+        // Proper texture is rendered in `update_fog`, once the role of the local player is known
+        let texture = images.add(fog_image(fog, 255, None, None));
+
+        let material = materials.add(StandardMaterial {
+            unlit: true,
+            base_color_texture: Some(texture.clone()),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        });
+
+        commands.entity(entity).insert((
+            PbrBundle {
+                mesh: meshes.add(Rectangle::new(1.0, 1.0)),
+                material,
+                transform: fog_transform(fog),
+                visibility: fog_visibility(fog, None),
+                ..default()
+            },
+            FogTexture(texture),
+            FogMaskCache::default(),
+            // Fog covers the whole table, tokens under it still have to be draggable
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+/// Re-renders the fog of the local player's scene when it changes, when they switch to its scene,
+/// when they become or stop being the GM, or when something changes what their tokens can see
+fn update_fog(
+    mut fogs: Query<
+        (
+            Entity,
+            Ref<FogOfWar>,
+            &FogTexture,
+            &mut FogMaskCache,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        Without<OffScene>,
+    >,
+    viewers: Query<(&Token, &Sight, &TokenOwners), Without<OffScene>>,
+    walls: Query<&Wall, Without<OffScene>>,
+    mut back_on_scene: RemovedComponents<OffScene>,
+    mut images: ResMut<Assets<Image>>,
+    mut last_state: Local<Option<(bool, Vec<(Vec2, f32)>, Vec<Wall>)>>,
+    client_id: Res<ClientId>,
+    roles: Res<PlayerRoles>,
+    server_state: Res<State<server::NetworkingState>>,
+) {
+    let is_gm = local_role(&client_id, &roles, &server_state) == Role::Gm;
+
+    let eyes = viewers
+        .iter()
+        .filter(|(_, _, owners)| owners.contains(&client_id.0))
+        .map(|(token, sight, _)| (token.position, sight.radius))
+        .collect::<Vec<_>>();
+    let walls = walls.iter().copied().collect::<Vec<_>>();
+    let back_on_scene = back_on_scene.read().collect::<Vec<_>>();
+
+    let state = Some((is_gm, eyes, walls));
+    let state_changed = *last_state != state;
+    *last_state = state;
+    let Some((_, eyes, walls)) = last_state.as_ref() else {
+        return;
+    };
+
+    let darkness = if is_gm { GM_FOG_ALPHA } else { 255 };
+
+    for (entity, fog, texture, mut cache, mut transform, mut visibility) in fogs.iter_mut() {
+        if !fog.is_changed() && !state_changed && !back_on_scene.contains(&entity) {
+            continue;
+        }
+
+        // GM sees everything, vision only limits players
+        let vision = (fog.vision && !is_gm).then(|| {
+            let segments = sight_blockers(walls);
+            eyes.iter()
+                .map(|(position, radius)| visibility_polygon(*position, *radius, &segments))
+                .collect::<Vision>()
+        });
+
+        *transform = fog_transform(&fog);
+        *visibility = fog_visibility(&fog, vision.as_ref());
+
+        let fog_mask = fog.enabled.then(|| cache.update(&fog));
+        if let Some(image) = images.get_mut(&texture.0) {
+            *image = fog_image(&fog, darkness, fog_mask, vision.as_ref());
+        }
+    }
+}
+
+/// Left drag paints with the brush. Polygon corners are placed with left clicks and
+/// finished with right click or Enter, Escape discards them
+fn paint_fog(
+    mut tool: ResMut<FogTool>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    egui: Query<&EguiContext>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    let cursor = cursor_pos.world_position;
+    let keyboard = !egui.single().get().wants_keyboard_input();
+
+    if keyboard && key_input.just_pressed(KeyCode::Escape) {
+        tool.points.clear();
+        return;
+    }
+
+    match tool.mode {
+        FogMode::RevealBrush | FogMode::HideBrush => {
+            if mouse_input.just_pressed(MouseButton::Left) && !**over_ui {
+                tool.points = vec![cursor];
+            }
+
+            if tool.points.is_empty() {
+                return;
+            }
+
+            if mouse_input.pressed(MouseButton::Left) {
+                let spacing = tool.brush_radius / 3.0;
+                if tool
+                    .points
+                    .last()
+                    .is_some_and(|last| last.distance(cursor) > spacing)
+                {
+                    tool.points.push(cursor);
+                }
+
+                // Long strokes are sent in pieces, the server refuses longer shapes
+                if tool.points.len() >= MAX_FOG_SHAPE_POINTS {
+                    let points = std::mem::replace(&mut tool.points, vec![cursor]);
+                    send_brush(&mut connection, &tool, points);
+                }
+                return;
+            }
+
+            let points = std::mem::take(&mut tool.points);
+            send_brush(&mut connection, &tool, points);
+        }
+        FogMode::RevealPolygon | FogMode::HidePolygon => {
+            if mouse_input.just_pressed(MouseButton::Left)
+                && !**over_ui
+                && tool.points.len() < MAX_FOG_SHAPE_POINTS
+            {
+                tool.points.push(cursor);
+            }
+
+            let finish = (mouse_input.just_pressed(MouseButton::Right) && !**over_ui)
+                || (keyboard && key_input.just_pressed(KeyCode::Enter));
+
+            if finish && tool.points.len() >= 3 {
+                let shape = FogShape::Polygon(std::mem::take(&mut tool.points));
+                _ = connection
+                    .send_message::<UnorderedReliable, _>(&FogMessage::Apply(tool.mode.op(shape)));
+            }
+        }
+    }
+}
+
+fn send_brush(connection: &mut client::ConnectionManager, tool: &FogTool, points: Vec<Vec2>) {
+    let shape = FogShape::Brush {
+        points,
+        radius: tool.brush_radius,
+    };
+    _ = connection.send_message::<UnorderedReliable, _>(&FogMessage::Apply(tool.mode.op(shape)));
+}
+
+fn draw_fog_tool(
+    mut gizmos: Gizmos,
+    mut tool: ResMut<FogTool>,
+    active_tool: Res<Tool>,
+    cursor_pos: Res<CursorPosition>,
+    player: Res<Player>,
+) {
+    if *active_tool != Tool::Fog {
+        if !tool.points.is_empty() {
+            tool.points.clear();
+        }
+        return;
+    }
+
+    let color = Color::rgb_u8(player.color[0], player.color[1], player.color[2]);
+    let cursor = cursor_pos.world_position;
+
+    match tool.mode {
+        FogMode::RevealBrush | FogMode::HideBrush => {
+            for point in tool.points.iter().chain(std::iter::once(&cursor)) {
+                gizmos.circle(
+                    point.extend(FOG_LAYER + 0.1),
+                    Direction3d::Z,
+                    tool.brush_radius,
+                    color,
+                );
+            }
+        }
+        FogMode::RevealPolygon | FogMode::HidePolygon => {
+            if tool.points.is_empty() {
+                return;
+            }
+            gizmos.linestrip(
+                tool.points
+                    .iter()
+                    .chain([&cursor, &tool.points[0]])
+                    .map(|point| point.extend(FOG_LAYER + 0.1)),
+                color,
+            );
+        }
+    }
+}
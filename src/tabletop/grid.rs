@@ -0,0 +1,408 @@
+use bevy_infinite_grid::InfiniteGridSettings;
+use std::f32::consts::{FRAC_PI_3, FRAC_PI_6};
+
+use crate::{
+    prelude::*,
+    tabletop::{scenes::OffScene, TopdownCamera},
+};
+
+pub struct GridPlugin;
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (apply_grid_settings, draw_hex_grid));
+    }
+}
+
+/// Hex grids are drawn with gizmos, so stop drawing them when zoomed out this far
+const MAX_DRAWN_HEXES: f32 = 6000.0;
+
+impl GridSettings {
+    /// Snaps world position to the grid, according to the [`SnapMode`]
+    pub fn snap(&self, position: Vec2) -> Vec2 {
+        match self.snap {
+            SnapMode::Centers => self.snap_to_center(position),
+            SnapMode::Intersections => self.snap_to_intersection(position),
+            SnapMode::Disabled => position,
+        }
+    }
+
+    /// Center of the cell that contains given world position
+    pub fn snap_to_center(&self, position: Vec2) -> Vec2 {
+        let local = position - self.origin;
+        let snapped = match self.kind {
+            GridKind::Square => {
+                (local / self.cell_size).floor() * self.cell_size + self.cell_size / 2.0
+            }
+            GridKind::HexPointy | GridKind::HexFlat => {
+                self.hex_to_world(self.world_to_hex(position)) - self.origin
+            }
+            GridKind::Gridless => local,
+        };
+        snapped + self.origin
+    }
+
+    /// Closest corner of a cell to the given world position
+    pub fn snap_to_intersection(&self, position: Vec2) -> Vec2 {
+        match self.kind {
+            GridKind::Square => {
+                let local = position - self.origin;
+                (local / self.cell_size).round() * self.cell_size + self.origin
+            }
+            GridKind::HexPointy | GridKind::HexFlat => {
+                let center = self.snap_to_center(position);
+                self.hex_corners(center)
+                    .into_iter()
+                    .min_by(|a, b| {
+                        a.distance_squared(position)
+                            .total_cmp(&b.distance_squared(position))
+                    })
+                    .unwrap_or(center)
+            }
+            GridKind::Gridless => position,
+        }
+    }
+
+    /// Snaps the center of a token so its footprint lines up with the cells. Tokens spanning
+    /// an even number of cells end up on an intersection, odd ones on a cell center.
+    /// Only square grids snapping to centers have footprints, everything else snaps as usual
+    pub fn snap_token(&self, position: Vec2, size: TokenSize) -> Vec2 {
+        if self.kind != GridKind::Square || self.snap != SnapMode::Centers {
+            return self.snap(position);
+        }
+
+        // Tokens smaller than a cell sit in the middle of one
+        let half_footprint =
+            Vec2::new(size.width, size.height).max(Vec2::ONE) * self.cell_size / 2.0;
+        let corner = self.snap_to_intersection(position - half_footprint);
+        corner + half_footprint
+    }
+
+    /// Distance from hex center to its corners
+    pub fn hex_radius(&self) -> f32 {
+        self.cell_size / 3f32.sqrt()
+    }
+
+    /// Axial coordinates of the hex that contains given world position
+    pub fn world_to_hex(&self, position: Vec2) -> IVec2 {
+        let local = (position - self.origin) / self.hex_radius();
+        let sqrt3 = 3f32.sqrt();
+
+        let axial = match self.kind {
+            GridKind::HexFlat => {
+                Vec2::new(2.0 / 3.0 * local.x, -local.x / 3.0 + sqrt3 / 3.0 * local.y)
+            }
+            _ => Vec2::new(sqrt3 / 3.0 * local.x - local.y / 3.0, 2.0 / 3.0 * local.y),
+        };
+
+        round_axial(axial)
+    }
+
+    /// Center of the hex with given axial coordinates
+    pub fn hex_to_world(&self, hex: IVec2) -> Vec2 {
+        let (q, r) = (hex.x as f32, hex.y as f32);
+        let sqrt3 = 3f32.sqrt();
+
+        let local = match self.kind {
+            GridKind::HexFlat => Vec2::new(1.5 * q, sqrt3 / 2.0 * q + sqrt3 * r),
+            _ => Vec2::new(sqrt3 * q + sqrt3 / 2.0 * r, 1.5 * r),
+        };
+
+        local * self.hex_radius() + self.origin
+    }
+
+    pub fn hex_corners(&self, center: Vec2) -> [Vec2; 6] {
+        let offset = match self.kind {
+            GridKind::HexFlat => 0.0,
+            _ => FRAC_PI_6,
+        };
+
+        std::array::from_fn(|i| {
+            let angle = FRAC_PI_3 * i as f32 + offset;
+            center + Vec2::from_angle(angle) * self.hex_radius()
+        })
+    }
+
+    /// Length of a path in grid units (e.g. feet), following the diagonal rule of the grid
+    pub fn measure(&self, points: &[Vec2]) -> f32 {
+        let segments = points.windows(2).map(|segment| (segment[0], segment[1]));
+
+        let cells = match self.kind {
+            GridKind::Gridless => {
+                segments.map(|(from, to)| from.distance(to)).sum::<f32>() / self.cell_size
+            }
+            GridKind::HexPointy | GridKind::HexFlat => segments
+                .map(|(from, to)| hex_distance(self.world_to_hex(from), self.world_to_hex(to)))
+                .sum::<i32>() as f32,
+            GridKind::Square => {
+                // Alternating rule counts diagonals over the whole path, not per segment
+                let mut diagonals_so_far = 0;
+                let mut total = 0.0;
+
+                for (from, to) in segments {
+                    let cell =
+                        |point: Vec2| ((point - self.origin) / self.cell_size).floor().as_ivec2();
+                    let delta = (cell(to) - cell(from)).abs();
+                    let diagonal = delta.min_element();
+                    let straight = delta.max_element() - diagonal;
+
+                    total += match self.diagonals {
+                        DiagonalRule::Euclidean => from.distance(to) / self.cell_size,
+                        DiagonalRule::Chebyshev => (straight + diagonal) as f32,
+                        DiagonalRule::Alternating => {
+                            let extra = (diagonals_so_far + diagonal) / 2 - diagonals_so_far / 2;
+                            diagonals_so_far += diagonal;
+                            (straight + diagonal + extra) as f32
+                        }
+                    };
+                }
+
+                total
+            }
+        };
+
+        cells * self.cell_distance
+    }
+
+    pub fn line_color(&self) -> Color {
+        Color::rgba_u8(
+            self.color[0],
+            self.color[1],
+            self.color[2],
+            (self.opacity.clamp(0.0, 1.0) * 255.0) as u8,
+        )
+    }
+}
+
+fn hex_distance(from: IVec2, to: IVec2) -> i32 {
+    let delta = to - from;
+    (delta.x.abs() + delta.y.abs() + (delta.x + delta.y).abs()) / 2
+}
+
+/// Rounds fractional axial coordinates through cube coordinates
+fn round_axial(axial: Vec2) -> IVec2 {
+    let cube = Vec3::new(axial.x, axial.y, -axial.x - axial.y);
+    let mut rounded = cube.round();
+    let diff = (rounded - cube).abs();
+
+    if diff.x > diff.y && diff.x > diff.z {
+        rounded.x = -rounded.y - rounded.z;
+    } else if diff.y > diff.z {
+        rounded.y = -rounded.x - rounded.z;
+    }
+
+    IVec2::new(rounded.x as i32, rounded.y as i32)
+}
+
+/// Grids of the scene the local player is on
+pub type Grids<'w, 's> = Query<'w, 's, &'static GridSettings, Without<OffScene>>;
+
+/// Grid of the table, or the default one when not connected anywhere
+pub fn current_grid(grids: &Grids) -> GridSettings {
+    grids.iter().next().cloned().unwrap_or_default()
+}
+
+/// Follows changes of the grid, and of the scene it belongs to
+fn apply_grid_settings(
+    grids: Grids,
+    mut infinite_grid: Query<(&mut InfiniteGridSettings, &mut Transform, &mut Visibility)>,
+    mut applied: Local<Option<GridSettings>>,
+) {
+    let Some(grid) = grids.iter().next() else {
+        return;
+    };
+
+    if applied.as_ref() == Some(grid) {
+        return;
+    }
+    *applied = Some(grid.clone());
+
+    for (mut settings, mut transform, mut visibility) in infinite_grid.iter_mut() {
+        let color = grid.line_color();
+        settings.x_axis_color = color;
+        settings.z_axis_color = color;
+        settings.major_line_color = color;
+        settings.minor_line_color = color;
+        settings.scale = 1.0 / grid.cell_size;
+
+        transform.translation.x = grid.origin.x;
+        transform.translation.y = grid.origin.y;
+
+        *visibility = match grid.kind {
+            GridKind::Square => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn draw_hex_grid(
+    mut gizmos: Gizmos,
+    grids: Grids,
+    camera: Query<(&GlobalTransform, &Projection), With<TopdownCamera>>,
+) {
+    let grid = current_grid(&grids);
+
+    if !matches!(grid.kind, GridKind::HexPointy | GridKind::HexFlat) || grid.opacity <= 0.0 {
+        return;
+    }
+
+    let Ok((camera_transform, Projection::Orthographic(projection))) = camera.get_single() else {
+        return;
+    };
+
+    let view = Rect {
+        min: projection.area.min + camera_transform.translation().xy(),
+        max: projection.area.max + camera_transform.translation().xy(),
+    };
+
+    let area = view.width() * view.height();
+    let hex_area = grid.cell_size * grid.cell_size * 3f32.sqrt() / 2.0;
+    if area / hex_area > MAX_DRAWN_HEXES {
+        return;
+    }
+
+    // Axial coordinates are skewed, so walk over the bounding box of all four view corners
+    let corners = [
+        view.min,
+        view.max,
+        Vec2::new(view.min.x, view.max.y),
+        Vec2::new(view.max.x, view.min.y),
+    ]
+    .map(|corner| grid.world_to_hex(corner));
+
+    let min = corners.iter().fold(IVec2::MAX, |acc, hex| acc.min(*hex)) - 1;
+    let max = corners.iter().fold(IVec2::MIN, |acc, hex| acc.max(*hex)) + 1;
+
+    let color = grid.line_color();
+    let margin = view.inset(grid.cell_size);
+
+    for q in min.x..=max.x {
+        for r in min.y..=max.y {
+            let center = grid.hex_to_world(IVec2::new(q, r));
+            if !margin.contains(center) {
+                continue;
+            }
+
+            let corners = grid.hex_corners(center);
+            gizmos.linestrip(
+                corners
+                    .iter()
+                    .chain(std::iter::once(&corners[0]))
+                    .map(|corner| corner.extend(10.0)),
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square_grid(cell_size: f32, origin: Vec2) -> GridSettings {
+        GridSettings {
+            cell_size,
+            origin,
+            ..default()
+        }
+    }
+
+    fn assert_snaps_to(grid: &GridSettings, position: Vec2, size: TokenSize, expected: Vec2) {
+        let snapped = grid.snap_token(position, size);
+        assert!(
+            snapped.abs_diff_eq(expected, 1e-5),
+            "{position} snapped to {snapped}, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn one_cell_token_snaps_to_cell_center() {
+        let grid = square_grid(1.0, Vec2::ZERO);
+        assert_snaps_to(
+            &grid,
+            Vec2::new(0.3, 0.8),
+            TokenSize::square(1.0),
+            Vec2::new(0.5, 0.5),
+        );
+        assert_snaps_to(
+            &grid,
+            Vec2::new(-1.2, 2.9),
+            TokenSize::square(1.0),
+            Vec2::new(-1.5, 2.5),
+        );
+    }
+
+    #[test]
+    fn even_token_snaps_to_intersection() {
+        let grid = square_grid(1.0, Vec2::ZERO);
+        assert_snaps_to(
+            &grid,
+            Vec2::new(1.2, 0.9),
+            TokenSize::square(2.0),
+            Vec2::new(1.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn odd_token_snaps_to_cell_center() {
+        let grid = square_grid(1.0, Vec2::ZERO);
+        assert_snaps_to(
+            &grid,
+            Vec2::new(1.4, 1.6),
+            TokenSize::square(3.0),
+            Vec2::new(1.5, 1.5),
+        );
+    }
+
+    #[test]
+    fn tiny_token_sits_in_a_whole_cell() {
+        let grid = square_grid(1.0, Vec2::ZERO);
+        assert_snaps_to(
+            &grid,
+            Vec2::new(0.3, 0.8),
+            TokenSize::square(0.5),
+            Vec2::new(0.5, 0.5),
+        );
+    }
+
+    #[test]
+    fn small_token_snaps_like_medium() {
+        let grid = square_grid(1.0, Vec2::ZERO);
+        let small = TokenSize::from(SizeCategory::Small);
+        assert_eq!(small.category(), Some(SizeCategory::Small));
+        assert_eq!(
+            TokenSize::square(1.0).category(),
+            Some(SizeCategory::Medium)
+        );
+        assert_snaps_to(&grid, Vec2::new(0.3, 0.8), small, Vec2::new(0.5, 0.5));
+    }
+
+    #[test]
+    fn footprint_follows_origin_and_cell_size() {
+        let grid = square_grid(2.0, Vec2::new(0.25, -0.5));
+        assert_snaps_to(
+            &grid,
+            Vec2::new(1.0, 0.1),
+            TokenSize::square(1.0),
+            Vec2::new(1.25, 0.5),
+        );
+        assert_snaps_to(
+            &grid,
+            Vec2::new(3.0, 2.0),
+            TokenSize::square(2.0),
+            Vec2::new(2.25, 1.5),
+        );
+    }
+
+    #[test]
+    fn other_grids_snap_as_usual() {
+        let grid = GridSettings {
+            kind: GridKind::HexPointy,
+            ..default()
+        };
+        let position = Vec2::new(0.7, 1.3);
+        assert_eq!(
+            grid.snap_token(position, TokenSize::square(2.0)),
+            grid.snap(position)
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+
+use crate::prelude::*;
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, send_history_requests);
+    }
+}
+
+/// Ctrl + Z undoes the last edit, Ctrl + Y or Ctrl + Shift + Z makes it again
+fn send_history_requests(
+    key_input: Res<ButtonInput<KeyCode>>,
+    egui: Query<&EguiContext>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    // Text fields have their own undo
+    if egui.single().get().wants_keyboard_input() {
+        return;
+    }
+
+    if !key_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    let shift = key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    let message = if key_input.just_pressed(KeyCode::KeyY)
+        || (shift && key_input.just_pressed(KeyCode::KeyZ))
+    {
+        HistoryMessage::Redo
+    } else if key_input.just_pressed(KeyCode::KeyZ) {
+        HistoryMessage::Undo
+    } else {
+        return;
+    };
+
+    _ = connection.send_message::<UnorderedReliable, _>(&message);
+}
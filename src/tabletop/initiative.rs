@@ -0,0 +1,43 @@
+use crate::{
+    prelude::*,
+    tabletop::{scenes::OffScene, selection::token_rect},
+};
+
+pub struct InitiativePlugin;
+impl Plugin for InitiativePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, highlight_active_token);
+    }
+}
+
+const ACTIVE_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+
+/// Pulsing frame around the token whose turn it is
+fn highlight_active_token(
+    mut gizmos: Gizmos,
+    trackers: Query<&Initiative, Without<OffScene>>,
+    tokens: Query<&GlobalTransform, (With<Token>, Without<OffScene>)>,
+    time: Res<Time>,
+) {
+    for initiative in trackers.iter() {
+        let Some(active) = initiative.active() else {
+            continue;
+        };
+
+        let Ok(transform) = tokens.get(active.token) else {
+            continue;
+        };
+
+        let pulse = (time.elapsed_seconds() * 3.0).sin() * 0.5 + 0.5;
+        let rect = token_rect(transform);
+
+        for offset in [0.12, 0.16] {
+            gizmos.rect(
+                rect.center().extend(transform.translation().z + 0.1),
+                Quat::IDENTITY,
+                rect.size() + Vec2::splat(offset + pulse * 0.08),
+                ACTIVE_COLOR,
+            );
+        }
+    }
+}
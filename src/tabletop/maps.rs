@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    render::{
+        render_asset::RenderAssetUsages,
+        texture::{CompressedImageFormats, ImageSampler, ImageType},
+    },
+    window::FileDragAndDrop,
+};
+use lightyear::prelude::*;
+
+use crate::{input::CursorPosition, networking::asset_sharing::SharedAssets, prelude::*};
+
+pub struct MapPlugin;
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ImportMap>().add_systems(
+            Update,
+            (drop_map_files, import_maps)
+                .chain()
+                .run_if(in_state(server::NetworkingState::Started)),
+        );
+    }
+}
+
+/// How many image pixels make up a grid cell on imported maps
+const PIXELS_PER_CELL: f32 = 100.0;
+
+/// Request to load an image from disk and put it on the table as a new map
+#[derive(Event, Debug, Clone)]
+pub struct ImportMap {
+    pub path: PathBuf,
+    /// Center of the new map on the tabletop
+    pub position: Vec2,
+}
+
+/// Maps can only be added by the host, since the image lives on the host's disk
+fn drop_map_files(
+    mut dropped: EventReader<FileDragAndDrop>,
+    mut import: EventWriter<ImportMap>,
+    cursor_pos: Res<CursorPosition>,
+) {
+    for dropped in dropped.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = dropped else {
+            continue;
+        };
+
+        import.send(ImportMap {
+            path: path_buf.clone(),
+            position: cursor_pos.world_position,
+        });
+    }
+}
+
+fn load_image(path: &Path) -> Result<Image, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .ok_or_else(|| String::from("file has no extension"))?;
+
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension(extension),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::default(),
+    )
+    .map_err(|err| err.to_string())
+}
+
+fn import_maps(
+    mut commands: Commands,
+    mut imports: EventReader<ImportMap>,
+    maps: Query<(), With<MapBackground>>,
+    mut images: ResMut<Assets<Image>>,
+    mut shared_images: ResMut<SharedAssets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut count = maps.iter().count();
+
+    for import in imports.read() {
+        let image = match load_image(&import.path) {
+            Ok(image) => image,
+            Err(err) => {
+                error!("Failed to import map {:?}: {err}", import.path);
+                continue;
+            }
+        };
+
+        let size = image.size_f32() / PIXELS_PER_CELL;
+        let (image, image_id) = shared_images.add_shared(&mut images, image);
+
+        let material = materials.add(StandardMaterial {
+            unlit: true,
+            base_color_texture: Some(image),
+            ..default()
+        });
+
+        // Newer maps go on top, but always stay under the grid
+        let map = MapBackground {
+            position: import.position,
+            size,
+            layer: (count as f32 * 0.01).min(9.0),
+        };
+        count += 1;
+
+        info!("Imported map {:?}", import.path);
+
+        commands.spawn((
+            Name::new("Map background"),
+            PbrBundle {
+                transform: map.transform(),
+                mesh: meshes.add(Mesh::from(Rectangle::new(1.0, 1.0))),
+                material,
+                ..default()
+            },
+            map,
+            SharedAsset::<Image>::new(image_id),
+            server::Replicate {
+                target: ReplicationTarget {
+                    target: NetworkTarget::None,
+                },
+                ..default()
+            },
+        ));
+    }
+}
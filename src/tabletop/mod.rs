@@ -0,0 +1,684 @@
+use bevy::{
+    input::mouse::MouseWheel,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+    window::PrimaryWindow,
+};
+use bevy_egui::EguiContext;
+use bevy_infinite_grid::{InfiniteGridBundle, InfiniteGridSettings};
+use lightyear::prelude::*;
+use picking_core::PickingPluginsSettings;
+use pointer::InputMove;
+
+use crate::{
+    input::{CursorPosition, OverUI},
+    networking::{
+        asset_sharing::{RequestAssetMessage, SharedAssets},
+        autosave::SpawnDefaults,
+        tokens::DEFAULT_TOKEN_LAYER,
+    },
+    prelude::*,
+};
+use selection::Selected;
+
+pub mod bars;
+pub mod conditions;
+pub mod drawing;
+pub mod fog;
+pub mod grid;
+pub mod history;
+pub mod initiative;
+pub mod maps;
+pub mod ping;
+pub mod ruler;
+pub mod scenes;
+pub mod selection;
+pub mod spawning;
+pub mod templates;
+pub mod tools;
+pub mod walls;
+
+pub struct TabletopPlugin;
+impl Plugin for TabletopPlugin {
+    fn build(&self, app: &mut App) {
+        let is_headless = std::env::args().any(|arg| arg == "--headless");
+        
+        if !is_headless {
+            app.insert_resource(Msaa::Sample4)
+                .register_type::<Moving>()
+                .init_resource::<TokenDrag>()
+                .add_event::<TokenDragged>()
+                .add_event::<TokenDropped>()
+                .add_event::<TokenClicked>()
+                .add_plugins((
+                    tools::ToolsPlugin,
+                    selection::SelectionPlugin,
+                    grid::GridPlugin,
+                    ruler::RulerPlugin,
+                    templates::TemplatePlugin,
+                    fog::FogPlugin,
+                    walls::WallPlugin,
+                    drawing::DrawingPlugin,
+                    ping::PingPlugin,
+                    conditions::ConditionPlugin,
+                    bars::BarPlugin,
+                    initiative::InitiativePlugin,
+                    maps::MapPlugin,
+                    (
+                        scenes::ScenePlugin,
+                        history::HistoryPlugin,
+                        spawning::TokenSpawnPlugin,
+                    ),
+                ))
+                .add_systems(
+                    OnEnter(lightyear::prelude::server::NetworkingState::Started),
+                    spawn_tokens.run_if(run_once()).in_set(SpawnDefaults),
+                )
+                .add_systems(
+                    Update,
+                    init_loaded_entities
+                        .run_if(in_state(lightyear::prelude::server::NetworkingState::Started)),
+                )
+                .add_systems(
+                    PreUpdate,
+                    (
+                        drop_moving_tokens.after(MainSet::EmitEvents),
+                        update_picking.after(crate::input::update_over_ui),
+                        update_world_cursor.after(crate::input::update_cursor_position),
+                    ),
+                )
+                .add_systems(
+                    Update,
+                    (
+                        init_token_picking,
+                        (
+                            start_moving_tokens,
+                            move_tokens,
+                            drop_tokens,
+                            cancel_moving_tokens,
+                            reset_token_drag,
+                        )
+                            .chain()
+                            .after(selection::select_tokens),
+                        move_tabletop,
+                        zoom_tabletop,
+                        scale_tokens,
+                        update_token_images,
+                    ),
+                );
+        }
+        
+        app.add_systems(Startup, spawn_tabletop);
+    }
+}
+
+/// Token that is being dragged by the local player.
+/// While present, the token's `Transform` is driven locally instead of by [`Token::position`]
+#[derive(Component, Reflect, Clone, Copy, Default)]
+pub struct Moving {
+    start_pos: Vec2,
+    last_sent: Vec2,
+}
+
+/// Drag shared by every [`Moving`] token, so the whole selection moves by the same offset
+#[derive(Resource, Default)]
+struct TokenDrag {
+    lead: Option<Entity>,
+    delta: Vec2,
+}
+
+#[derive(Event, Clone, Copy)]
+struct TokenDragged {
+    entity: Entity,
+    button: PointerButton,
+}
+
+impl From<ListenerInput<Pointer<DragStart>>> for TokenDragged {
+    fn from(event: ListenerInput<Pointer<DragStart>>) -> Self {
+        Self {
+            entity: event.target,
+            button: event.button,
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy)]
+struct TokenDropped {
+    entity: Entity,
+    button: PointerButton,
+}
+
+impl From<ListenerInput<Pointer<DragEnd>>> for TokenDropped {
+    fn from(event: ListenerInput<Pointer<DragEnd>>) -> Self {
+        Self {
+            entity: event.target,
+            button: event.button,
+        }
+    }
+}
+
+/// Token clicked by the local player, used to open the token menu
+#[derive(Event, Clone, Copy)]
+pub struct TokenClicked {
+    pub entity: Entity,
+    pub button: PointerButton,
+    /// Screen position of the click
+    pub position: Vec2,
+}
+
+impl From<ListenerInput<Pointer<Click>>> for TokenClicked {
+    fn from(event: ListenerInput<Pointer<Click>>) -> Self {
+        Self {
+            entity: event.target,
+            button: event.button,
+            position: event.pointer_location.position,
+        }
+    }
+}
+
+fn init_token_picking(mut commands: Commands, tokens: Query<Entity, Added<Token>>) {
+    for entity in tokens.iter() {
+        commands.entity(entity).insert((
+            On::<Pointer<DragStart>>::send_event::<TokenDragged>(),
+            On::<Pointer<DragEnd>>::send_event::<TokenDropped>(),
+            On::<Pointer<Click>>::send_event::<TokenClicked>(),
+        ));
+    }
+}
+
+fn drop_moving_tokens(
+    mut commands: Commands,
+    tokens: Query<Entity, Or<(With<Moving>, With<Selected>)>>,
+    mut deselect_events: EventReader<client::MessageEvent<DeselectMessage>>,
+) {
+    for event in deselect_events.read() {
+        match event.message {
+            DeselectMessage::Everything => {
+                for entity in tokens.iter() {
+                    commands.entity(entity).remove::<(Moving, Selected)>();
+                }
+            }
+            DeselectMessage::Entity(entity) => {
+                if let Some(mut entity) = commands.get_entity(entity) {
+                    entity.remove::<(Moving, Selected)>();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Component, Reflect, Clone, Copy, Default)]
+pub struct TopdownCamera;
+
+/// Flat mesh of a convex polygon, facing the `TopdownCamera`
+pub fn polygon_mesh(points: &[Vec2]) -> Mesh {
+    let positions = points
+        .iter()
+        .map(|point| [point.x, point.y, 0.0])
+        .collect::<Vec<_>>();
+    let indices = (1..points.len().saturating_sub(1) as u32)
+        .flat_map(|i| [0, i, i + 1])
+        .collect();
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0f32, 0.0, 1.0]; points.len()])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0f32, 0.0]; points.len()])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Picking is off over the UI, and while Alt is held so pings don't grab tokens
+fn update_picking(
+    mut picking_settings: ResMut<PickingPluginsSettings>,
+    movable: Query<&Moving>,
+    over_ui: Res<OverUI>,
+    key_input: Res<ButtonInput<KeyCode>>,
+) {
+    let pinging = key_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    picking_settings.is_enabled = (!**over_ui && !pinging) || !movable.is_empty();
+}
+
+fn update_world_cursor(
+    mut cursor_pos: ResMut<CursorPosition>,
+    camera: Query<(&Camera, &GlobalTransform), With<TopdownCamera>>,
+) {
+    let Ok((camera, camera_transform)) = camera.get_single() else {
+        return;
+    };
+
+    // Only touched when it moves, so the replicated local cursor isn't sent every frame
+    if let Some(position) = camera.viewport_to_world_2d(camera_transform, cursor_pos.position) {
+        if cursor_pos.world_position != position {
+            cursor_pos.world_position = position;
+        }
+    }
+}
+
+/// Starts moving the whole selection when one of the selected tokens is dragged
+fn start_moving_tokens(
+    mut commands: Commands,
+    mut dragged: EventReader<TokenDragged>,
+    // Locked tokens stay where they are, even when selected along with others
+    tokens: Query<&Transform, (With<Token>, Without<TokenLocked>)>,
+    selected: Query<Entity, With<Selected>>,
+    mut drag: ResMut<TokenDrag>,
+    mut connection: ResMut<client::ConnectionManager>,
+    tool: Res<tools::Tool>,
+) {
+    for dragged in dragged.read() {
+        if dragged.button != PointerButton::Primary
+            || drag.lead.is_some()
+            || *tool != tools::Tool::Select
+        {
+            continue;
+        }
+
+        *drag = TokenDrag {
+            lead: Some(dragged.entity),
+            delta: Vec2::ZERO,
+        };
+
+        let targets = selected
+            .iter()
+            .chain(std::iter::once(dragged.entity))
+            .collect::<bevy::utils::HashSet<_>>();
+
+        for entity in targets {
+            let Ok(transform) = tokens.get(entity) else {
+                continue;
+            };
+
+            let start_pos = transform.translation.xy();
+            commands.entity(entity).insert(Moving {
+                start_pos,
+                last_sent: start_pos,
+            });
+            _ = connection.send_message::<OrderedReliable, _>(&MoveTokenMessage::Start(entity));
+        }
+    }
+}
+
+fn move_tokens(
+    mut moving_targets: Query<(Entity, &mut Transform, &mut Moving, Option<&TokenSize>)>,
+    mut camera: Query<&mut Projection, With<TopdownCamera>>,
+    mut mouse_motion: EventReader<InputMove>,
+    mut drag: ResMut<TokenDrag>,
+    mut connection: ResMut<client::ConnectionManager>,
+    grids: grid::Grids,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+) {
+    let mouse_motion = mouse_motion.read().fold(Vec2::ZERO, |acc, x| acc + x.delta);
+
+    if mouse_input.pressed(MouseButton::Middle) {
+        return;
+    }
+
+    let mut projection = camera.single_mut();
+
+    let Projection::Orthographic(projection) = projection.as_mut() else {
+        return;
+    };
+
+    drag.delta.x += mouse_motion.x * projection.scale;
+    drag.delta.y -= mouse_motion.y * projection.scale;
+
+    // Snap the dragged token by its footprint and move everything else by the same offset,
+    // so tokens keep their positions relative to each other
+    let lead = drag
+        .lead
+        .and_then(|lead| moving_targets.get(lead).ok())
+        .or_else(|| moving_targets.iter().next())
+        .map(|(_, _, movement, size)| (movement.start_pos, size.copied().unwrap_or_default()));
+
+    let Some((lead_start, lead_size)) = lead else {
+        return;
+    };
+
+    let mut lead_pos = lead_start + drag.delta;
+    if !key_input.pressed(KeyCode::ShiftLeft) && !key_input.pressed(KeyCode::ShiftRight) {
+        lead_pos = grid::current_grid(&grids).snap_token(lead_pos, lead_size);
+    }
+    let offset = lead_pos - lead_start;
+
+    for (entity, mut transform, mut movement, _) in moving_targets.iter_mut() {
+        let new_pos = movement.start_pos + offset;
+        transform.translation = new_pos.extend(transform.translation.z);
+
+        if new_pos != movement.last_sent {
+            movement.last_sent = new_pos;
+            _ = connection.send_message::<SequencedUnreliable, _>(&MoveTokenMessage::Update(
+                entity, new_pos,
+            ));
+        }
+    }
+}
+
+fn drop_tokens(
+    mut commands: Commands,
+    mut dropped: EventReader<TokenDropped>,
+    moving_targets: Query<(Entity, &Transform), With<Moving>>,
+    mut drag: ResMut<TokenDrag>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    for dropped in dropped.read() {
+        if dropped.button != PointerButton::Primary || !moving_targets.contains(dropped.entity) {
+            continue;
+        }
+
+        for (entity, transform) in moving_targets.iter() {
+            let message = MoveTokenMessage::Commit(entity, transform.translation.xy());
+            _ = connection.send_message::<OrderedReliable, _>(&message);
+            commands.entity(entity).remove::<Moving>();
+        }
+
+        *drag = TokenDrag::default();
+    }
+}
+
+fn cancel_moving_tokens(
+    mut commands: Commands,
+    moving_targets: Query<Entity, With<Moving>>,
+    mut drag: ResMut<TokenDrag>,
+    mut connection: ResMut<client::ConnectionManager>,
+    key_input: Res<ButtonInput<KeyCode>>,
+) {
+    if !key_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    for entity in moving_targets.iter() {
+        _ = connection.send_message::<OrderedReliable, _>(&MoveTokenMessage::Cancel(entity));
+        commands.entity(entity).remove::<Moving>();
+    }
+
+    *drag = TokenDrag::default();
+}
+
+/// Forgets about the drag once the server has taken away all dragged tokens
+fn reset_token_drag(moving_targets: Query<(), With<Moving>>, mut drag: ResMut<TokenDrag>) {
+    if drag.lead.is_some() && moving_targets.is_empty() {
+        *drag = TokenDrag::default();
+    }
+}
+
+fn move_tabletop(
+    mut camera: Query<(&mut Transform, &Projection), With<TopdownCamera>>,
+    input: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<InputMove>,
+) {
+    let mouse_motion = mouse_motion.read().fold(Vec2::ZERO, |acc, x| acc + x.delta);
+
+    if !input.pressed(MouseButton::Middle) {
+        return;
+    }
+
+    let (mut transform, projection) = camera.single_mut();
+
+    let Projection::Orthographic(projection) = projection else {
+        return;
+    };
+
+    transform.translation.x += mouse_motion.x * -projection.scale;
+    transform.translation.y += mouse_motion.y * projection.scale;
+}
+
+#[derive(Clone, Copy, Deref, DerefMut)]
+pub struct ZoomLevel(f32);
+impl Default for ZoomLevel {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+
+fn zoom_tabletop(
+    mut camera: Query<(&mut Projection, &mut Transform), With<TopdownCamera>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    egui: Query<&EguiContext>,
+    cursor_pos: Res<CursorPosition>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut zoom_level: Local<ZoomLevel>,
+    mut skip_first: Local<bool>,
+) {
+    let window = window.single();
+    let window_size = Vec2::new(
+        window.resolution.physical_width() as f32,
+        window.resolution.physical_height() as f32,
+    );
+
+    if egui.single().get().is_pointer_over_area() {
+        return;
+    }
+
+    let mouse_wheel = mouse_wheel.read().fold(0.0, |acc, wheel| acc + wheel.y);
+
+    let (mut projection, mut transform) = camera.single_mut();
+
+    let Projection::Orthographic(projection) = projection.as_mut() else {
+        return;
+    };
+
+    let mut pos = cursor_pos.position;
+    pos.x -= window_size.x / 2.0;
+    pos.y = -pos.y + window_size.y / 2.0;
+
+    let zoom_before = projection.scale;
+    zoom_level.0 = f32::clamp(zoom_level.0 - mouse_wheel * 0.04, 0.0, 1.0);
+    projection.scale = <f32 as bevy::prelude::FloatExt>::lerp(0.002, 0.1, zoom_level.powi(2));
+    let zoom_delta = zoom_before - projection.scale;
+
+    // Fix camera moving when table loads
+    if !*skip_first {
+        *skip_first = true;
+        return;
+    }
+
+    transform.translation += pos.extend(0.0) * zoom_delta;
+}
+
+fn spawn_tokens(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut shared_images: ResMut<SharedAssets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let (map_image, map_image_id) = shared_images.load_shared(&asset_server, "map.png");
+    let bg_image = materials.add(StandardMaterial {
+        unlit: true,
+        base_color_texture: Some(map_image),
+        ..default()
+    });
+
+    let (image, image_id) = shared_images.load_shared(&asset_server, "token.png");
+
+    let token_material = materials.add(StandardMaterial {
+        unlit: true,
+        base_color_texture: Some(image),
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+
+    let quad = meshes.add(Mesh::from(Rectangle::new(1.0, 1.0)));
+
+    let map = MapBackground {
+        position: Vec2::ZERO,
+        size: Vec2::splat(12.0),
+        layer: 0.0,
+    };
+
+    commands.spawn((
+        Name::new("Map background"),
+        PbrBundle {
+            transform: map.transform(),
+            mesh: quad.clone(),
+            material: bg_image,
+            ..default()
+        },
+        map,
+        SharedAsset::<Image>::new(map_image_id),
+        server::Replicate {
+            target: ReplicationTarget {
+                target: NetworkTarget::None,
+            },
+            ..default()
+        },
+    ));
+
+    let mut token = commands.spawn_empty();
+    token.insert((
+        Name::new("Token"),
+        PbrBundle {
+            transform: Transform::from_scale(Vec3::new(0.95, 0.95, 1.0)),
+            mesh: quad.clone(),
+            material: token_material,
+            ..default()
+        },
+        Token {
+            position: Vec2::new(0.5, 0.5),
+            layer: DEFAULT_TOKEN_LAYER,
+        },
+        TokenName(String::from("Token")),
+        Sight::default(),
+        SharedAsset::<Image>::new(image_id),
+        server::Replicate {
+            target: ReplicationTarget {
+                target: NetworkTarget::None,
+            },
+            ..default()
+        },
+    ));
+}
+
+/// Tokens are a bit smaller than their footprint, so neighbours don't touch
+pub const TOKEN_MARGIN: f32 = 0.95;
+
+fn scale_tokens(
+    mut tokens: Query<(&mut Transform, Option<&TokenSize>), With<Token>>,
+    grids: grid::Grids,
+) {
+    let cell_size = grid::current_grid(&grids).cell_size;
+
+    for (mut transform, size) in tokens.iter_mut() {
+        let size = size.copied().unwrap_or_default();
+        let scale = (Vec2::new(size.width, size.height) * cell_size * TOKEN_MARGIN).extend(1.0);
+
+        // Leaves change detection alone for tokens that already fit
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
+
+/// Handle of a shared image. Host has the real one, clients only know it by its UUID
+pub fn shared_image_handle(shared_images: &SharedAssets<Image>, id: Uuid) -> Handle<Image> {
+    shared_images
+        .id_to_handle
+        .get(&id)
+        .cloned()
+        .unwrap_or_else(|| Handle::weak_from_u128(id.as_u128()))
+}
+
+fn update_token_images(
+    tokens: Query<
+        (&SharedAsset<Image>, &Handle<StandardMaterial>),
+        (With<Token>, Changed<SharedAsset<Image>>),
+    >,
+    shared_images: Res<SharedAssets<Image>>,
+    image_assets: Res<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    for (shared_image, material) in tokens.iter() {
+        let image = shared_image_handle(&shared_images, shared_image.id);
+        if image_assets.get(&image).is_none() {
+            _ = connection.send_message::<UnorderedReliable, _>(
+                &RequestAssetMessage::<Image>::new(shared_image.id),
+            );
+        }
+
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color_texture = Some(image);
+        }
+    }
+}
+
+/// Host never gets replicated entities, so tokens and maps loaded from a session are given meshes here
+fn init_loaded_entities(
+    mut commands: Commands,
+    tokens: Query<(Entity, &Token, &SharedAsset<Image>), Without<Handle<Mesh>>>,
+    maps: Query<(Entity, &MapBackground, &SharedAsset<Image>), Without<Handle<Mesh>>>,
+    shared_images: Res<SharedAssets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if tokens.is_empty() && maps.is_empty() {
+        return;
+    }
+
+    let quad = meshes.add(Mesh::from(Rectangle::new(1.0, 1.0)));
+
+    for (entity, token, shared_image) in tokens.iter() {
+        let token_material = materials.add(StandardMaterial {
+            unlit: true,
+            base_color_texture: shared_images.id_to_handle.get(&shared_image.id).cloned(),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        });
+
+        commands.entity(entity).insert(PbrBundle {
+            transform: Transform::from_translation(token.position.extend(token.layer)),
+            mesh: quad.clone(),
+            material: token_material,
+            ..default()
+        });
+    }
+
+    for (entity, map, shared_image) in maps.iter() {
+        let map_material = materials.add(StandardMaterial {
+            unlit: true,
+            base_color_texture: shared_images.id_to_handle.get(&shared_image.id).cloned(),
+            ..default()
+        });
+
+        commands.entity(entity).insert(PbrBundle {
+            transform: map.transform(),
+            mesh: quad.clone(),
+            material: map_material,
+            ..default()
+        });
+    }
+}
+
+fn spawn_tabletop(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Grid"),
+        InfiniteGridBundle {
+            settings: InfiniteGridSettings {
+                x_axis_color: Color::BLACK,
+                z_axis_color: Color::BLACK,
+                major_line_color: Color::BLACK,
+                minor_line_color: Color::BLACK,
+                ..default()
+            },
+            transform: Transform::from_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2))
+                .with_translation(Vec3::new(0.0, 0.0, 10.0)),
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        Name::new("Topdown camera"),
+        TopdownCamera,
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 0.0, 200.0),
+            projection: Projection::Orthographic(OrthographicProjection {
+                scale: 0.027,
+                ..default()
+            }),
+            ..default()
+        },
+    ));
+}
@@ -0,0 +1,114 @@
+use bevy::input::InputSystem;
+use lightyear::prelude::*;
+
+use crate::{
+    input::{CursorPosition, OverUI},
+    prelude::*,
+    tabletop::TopdownCamera,
+};
+
+pub struct PingPlugin;
+impl Plugin for PingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, send_pings.after(InputSystem))
+            .add_systems(PreUpdate, recieve_pings.after(MainSet::EmitEvents))
+            .add_systems(Update, draw_pings);
+    }
+}
+
+/// How long a ping stays on the table, in seconds
+const PING_DURATION: f32 = 2.0;
+
+/// Height pings are drawn at, above everything else on the table
+const PING_LAYER: f32 = 70.0;
+
+/// Animated marker left by a ping. Only exists locally
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Ping {
+    pub position: Vec2,
+    pub color: Color,
+    pub age: f32,
+}
+
+/// Alt + click pings a spot, GM can add Shift to bring everyone's camera there.
+/// The click is consumed, so tools don't see it. Token picking is turned off while Alt is held
+fn send_pings(
+    mut mouse_input: ResMut<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    if **over_ui || !key_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+        return;
+    }
+
+    if !mouse_input.clear_just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let message = PingMessage {
+        position: cursor_pos.world_position,
+        focus: key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+    };
+    _ = connection.send_message::<UnorderedReliable, _>(&message);
+}
+
+fn recieve_pings(
+    mut commands: Commands,
+    mut pings: EventReader<client::MessageEvent<PingedMessage>>,
+    mut camera: Query<&mut Transform, With<TopdownCamera>>,
+    player_data: Res<PlayerData>,
+) {
+    for ping in pings.read() {
+        let ping = ping.message;
+        let color = player_data
+            .get(&ping.client)
+            .map(|player| player.color)
+            .unwrap_or([255; 3]);
+
+        commands.spawn((
+            Name::new("Ping"),
+            Ping {
+                position: ping.position,
+                color: Color::rgb_u8(color[0], color[1], color[2]),
+                age: 0.0,
+            },
+        ));
+
+        if ping.focus {
+            for mut transform in camera.iter_mut() {
+                transform.translation.x = ping.position.x;
+                transform.translation.y = ping.position.y;
+            }
+        }
+    }
+}
+
+/// Rings spreading out from the pinged spot and fading away
+fn draw_pings(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    mut pings: Query<(Entity, &mut Ping)>,
+    time: Res<Time>,
+) {
+    for (entity, mut ping) in pings.iter_mut() {
+        ping.age += time.delta_seconds();
+
+        if ping.age >= PING_DURATION {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let progress = ping.age / PING_DURATION;
+        let position = ping.position.extend(PING_LAYER);
+
+        gizmos.circle(position, Direction3d::Z, 0.15, ping.color);
+
+        for ring in 0..3 {
+            let ring_progress = (progress * 2.0 - ring as f32 * 0.25).rem_euclid(1.0);
+            let color = ping.color.with_a(1.0 - progress);
+            gizmos.circle(position, Direction3d::Z, 0.2 + ring_progress, color);
+        }
+    }
+}
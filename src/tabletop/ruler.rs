@@ -0,0 +1,140 @@
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+
+use crate::{
+    input::{CursorPosition, OverUI},
+    prelude::*,
+    tabletop::{
+        grid::{current_grid, Grids},
+        tools::Tool,
+        TopdownCamera,
+    },
+};
+
+pub struct RulerPlugin;
+impl Plugin for RulerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_local_ruler)
+            .add_systems(Update, (update_local_ruler, draw_rulers).chain());
+    }
+}
+
+/// Ruler of the local player, the only one we are allowed to change
+#[derive(Component, Clone, Copy, Default)]
+pub struct LocalRuler;
+
+fn spawn_local_ruler(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Ruler"),
+        Ruler::default(),
+        LocalRuler,
+        client::Replicate::default(),
+    ));
+}
+
+fn update_local_ruler(
+    mut ruler: Query<&mut Ruler, With<LocalRuler>>,
+    grids: Grids,
+    tool: Res<Tool>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+) {
+    let Ok(mut ruler) = ruler.get_single_mut() else {
+        return;
+    };
+
+    if *tool != Tool::Ruler || !mouse_input.pressed(MouseButton::Left) {
+        if !ruler.points.is_empty() {
+            ruler.points.clear();
+        }
+        return;
+    }
+
+    let point = if key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        cursor_pos.world_position
+    } else {
+        current_grid(&grids).snap(cursor_pos.world_position)
+    };
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        if !**over_ui {
+            ruler.points = vec![point, point];
+        }
+        return;
+    }
+
+    if ruler.points.is_empty() {
+        return;
+    }
+
+    // Pin current end as a waypoint and keep measuring from it
+    if mouse_input.just_pressed(MouseButton::Right) || key_input.just_pressed(KeyCode::Space) {
+        ruler.points.push(point);
+    }
+
+    if ruler.points.last() != Some(&point) {
+        if let Some(end) = ruler.points.last_mut() {
+            *end = point;
+        }
+    }
+}
+
+fn draw_rulers(
+    mut gizmos: Gizmos,
+    rulers: Query<(&Ruler, Option<&Owner>)>,
+    camera: Query<(&Camera, &GlobalTransform), With<TopdownCamera>>,
+    egui: Query<&EguiContext>,
+    grids: Grids,
+    player_data: Res<PlayerData>,
+    player: Res<Player>,
+) {
+    let Ok((camera, camera_transform)) = camera.get_single() else {
+        return;
+    };
+
+    let grid = current_grid(&grids);
+    let painter = egui
+        .single()
+        .get()
+        .layer_painter(egui::LayerId::background());
+
+    for (ruler, owner) in rulers.iter() {
+        if ruler.points.len() < 2 {
+            continue;
+        }
+
+        let color = owner
+            .and_then(|owner| player_data.get(&owner.0))
+            .map(|player| player.color)
+            .unwrap_or(player.color);
+        let color = Color::rgb_u8(color[0], color[1], color[2]);
+
+        gizmos.linestrip(ruler.points.iter().map(|point| point.extend(55.0)), color);
+        for point in ruler.points.iter() {
+            gizmos.circle(point.extend(55.0), Direction3d::Z, 0.08, color);
+        }
+
+        let end = *ruler.points.last().unwrap();
+        let Some(label_pos) = camera.world_to_viewport(camera_transform, end.extend(55.0)) else {
+            continue;
+        };
+
+        let distance = grid.measure(&ruler.points);
+        let text = if distance.fract().abs() < 0.05 {
+            format!("{distance:.0} {}", grid.distance_unit)
+        } else {
+            format!("{distance:.1} {}", grid.distance_unit)
+        };
+
+        let [r, g, b, _] = color.as_rgba_u8();
+        painter.text(
+            egui::pos2(label_pos.x + 12.0, label_pos.y - 12.0),
+            egui::Align2::LEFT_BOTTOM,
+            text,
+            egui::FontId::proportional(18.0),
+            egui::Color32::from_rgb(r, g, b),
+        );
+    }
+}
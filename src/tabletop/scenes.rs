@@ -0,0 +1,61 @@
+use crate::{
+    networking::{client::ClientId, scenes::client_scene},
+    prelude::*,
+    tabletop::selection::Selected,
+};
+use lightyear::prelude::*;
+
+pub struct ScenePlugin;
+impl Plugin for ScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            hide_other_scenes.run_if(in_state(server::NetworkingState::Started)),
+        );
+    }
+}
+
+/// Entity of a scene the local player isn't on. Only the host ever has these,
+/// clients don't receive entities of other scenes at all
+#[derive(Component, Clone, Copy, Default)]
+pub struct OffScene;
+
+/// Meshes can be added after the entity got its scene, so visibility is checked every time.
+/// Entities on the scene are only touched when they come back, the fog hides itself when disabled
+fn hide_other_scenes(
+    mut commands: Commands,
+    mut entities: Query<(Entity, &InScene, Has<OffScene>, Option<&mut Visibility>)>,
+    scenes: Query<(Entity, &TableScene)>,
+    client_id: Res<ClientId>,
+) {
+    let scene = client_scene(scenes.iter(), client_id.0);
+
+    for (entity, in_scene, off_scene, visibility) in entities.iter_mut() {
+        let hide = scene != Some(in_scene.0);
+
+        if hide && !off_scene {
+            commands
+                .entity(entity)
+                .insert(OffScene)
+                .remove::<Selected>();
+        } else if !hide && off_scene {
+            commands.entity(entity).remove::<OffScene>();
+        }
+
+        let Some(mut visibility) = visibility else {
+            continue;
+        };
+
+        let new_visibility = if hide {
+            Visibility::Hidden
+        } else if off_scene {
+            Visibility::Inherited
+        } else {
+            continue;
+        };
+
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+    }
+}
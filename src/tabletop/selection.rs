@@ -0,0 +1,123 @@
+use crate::{
+    input::{CursorPosition, OverUI},
+    prelude::*,
+    tabletop::{
+        scenes::OffScene,
+        tools::{tool_active, Tool},
+    },
+};
+
+pub struct SelectionPlugin;
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Selected>()
+            .init_resource::<Marquee>()
+            .add_systems(
+                Update,
+                (select_tokens.run_if(tool_active(Tool::Select)), draw_selection).chain(),
+            );
+    }
+}
+
+/// Token selected by the local player. Selection is never replicated
+#[derive(Component, Reflect, Clone, Copy, Default)]
+pub struct Selected;
+
+/// Selection rectangle in world space, started by dragging from an empty spot on the table
+#[derive(Resource, Default)]
+pub struct Marquee {
+    pub start: Option<Vec2>,
+}
+
+/// Area covered by a token on the tabletop
+pub fn token_rect(transform: &GlobalTransform) -> Rect {
+    let (scale, _, translation) = transform.to_scale_rotation_translation();
+    Rect::from_center_size(translation.xy(), scale.xy())
+}
+
+pub fn select_tokens(
+    mut commands: Commands,
+    tokens: Query<
+        (Entity, &GlobalTransform, Has<Selected>),
+        (With<Token>, Without<OffScene>),
+    >,
+    mut marquee: ResMut<Marquee>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+) {
+    let cursor = cursor_pos.world_position;
+    let shift = key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if mouse_input.just_pressed(MouseButton::Left) && !**over_ui {
+        // Topmost token under the cursor
+        let hovered = tokens
+            .iter()
+            .filter(|(_, transform, _)| token_rect(transform).contains(cursor))
+            .max_by(|a, b| a.1.translation().z.total_cmp(&b.1.translation().z));
+
+        match hovered {
+            Some((entity, _, true)) if shift => {
+                commands.entity(entity).remove::<Selected>();
+            }
+            Some((entity, _, false)) => {
+                if !shift {
+                    for (other, _, _) in tokens.iter().filter(|(_, _, selected)| *selected) {
+                        commands.entity(other).remove::<Selected>();
+                    }
+                }
+                commands.entity(entity).insert(Selected);
+            }
+            // Clicking an already selected token keeps the selection, so it can be dragged as a group
+            Some((_, _, true)) => (),
+            None => {
+                if !shift {
+                    for (entity, _, _) in tokens.iter().filter(|(_, _, selected)| *selected) {
+                        commands.entity(entity).remove::<Selected>();
+                    }
+                }
+                marquee.start = Some(cursor);
+            }
+        }
+    }
+
+    if let Some(start) = marquee.start {
+        if !mouse_input.pressed(MouseButton::Left) {
+            let rect = Rect::from_corners(start, cursor);
+
+            for (entity, transform, _) in tokens.iter() {
+                if rect.contains(transform.translation().xy()) {
+                    commands.entity(entity).insert(Selected);
+                }
+            }
+
+            marquee.start = None;
+        }
+    }
+}
+
+fn draw_selection(
+    mut gizmos: Gizmos,
+    selected: Query<&GlobalTransform, With<Selected>>,
+    marquee: Res<Marquee>,
+    cursor_pos: Res<CursorPosition>,
+    player: Res<Player>,
+) {
+    let color = Color::rgb_u8(player.color[0], player.color[1], player.color[2]);
+
+    for transform in selected.iter() {
+        let rect = token_rect(transform);
+        gizmos.rect(
+            rect.center().extend(transform.translation().z + 0.1),
+            Quat::IDENTITY,
+            rect.size() + Vec2::splat(0.08),
+            color,
+        );
+    }
+
+    if let Some(start) = marquee.start {
+        let rect = Rect::from_corners(start, cursor_pos.world_position);
+        gizmos.rect(rect.center().extend(60.0), Quat::IDENTITY, rect.size(), color);
+    }
+}
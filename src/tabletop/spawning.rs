@@ -0,0 +1,101 @@
+use bevy::input::InputSystem;
+use lightyear::prelude::*;
+
+use crate::{
+    input::{CursorPosition, OverUI},
+    networking::{client::local_player_is_gm, tokens::DEFAULT_TOKEN_LAYER},
+    prelude::*,
+    tabletop::{
+        grid::{current_grid, Grids},
+        TOKEN_MARGIN,
+    },
+};
+
+pub struct TokenSpawnPlugin;
+impl Plugin for TokenSpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TokenPlacement>()
+            .add_systems(
+                PreUpdate,
+                place_tokens.after(InputSystem).run_if(local_player_is_gm),
+            )
+            .add_systems(Update, draw_placement);
+    }
+}
+
+/// Image the GM picked to put on the table, until placing is stopped
+#[derive(Resource, Debug, Default)]
+pub struct TokenPlacement {
+    pub image: Option<Uuid>,
+}
+
+/// Where a token dropped at the cursor goes, snapped by its footprint unless placed freely
+pub fn placement_position(
+    cursor_pos: &CursorPosition,
+    grids: &Grids,
+    size: TokenSize,
+    free: bool,
+) -> Vec2 {
+    if free {
+        cursor_pos.world_position
+    } else {
+        current_grid(grids).snap_token(cursor_pos.world_position, size)
+    }
+}
+
+/// Every click asks the server for a token at the cursor, snapped to the grid unless Shift is held.
+/// Right click or Escape stops placing. The clicks are consumed, so tools don't see them
+fn place_tokens(
+    mut placement: ResMut<TokenPlacement>,
+    mut mouse_input: ResMut<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    grids: Grids,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    let Some(image) = placement.image else {
+        return;
+    };
+
+    if key_input.just_pressed(KeyCode::Escape) || mouse_input.clear_just_pressed(MouseButton::Right)
+    {
+        placement.image = None;
+        return;
+    }
+
+    if **over_ui || !mouse_input.clear_just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let free = key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let message = TokenMessage::Spawn {
+        image,
+        position: placement_position(&cursor_pos, &grids, TokenSize::default(), free),
+    };
+    _ = connection.send_message::<UnorderedReliable, _>(&message);
+}
+
+/// Outline of the token about to be placed
+fn draw_placement(
+    mut gizmos: Gizmos,
+    placement: Res<TokenPlacement>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    grids: Grids,
+    key_input: Res<ButtonInput<KeyCode>>,
+) {
+    if placement.image.is_none() || **over_ui {
+        return;
+    }
+
+    let free = key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let position = placement_position(&cursor_pos, &grids, TokenSize::default(), free);
+
+    gizmos.rect(
+        position.extend(DEFAULT_TOKEN_LAYER),
+        Quat::IDENTITY,
+        Vec2::splat(current_grid(&grids).cell_size * TOKEN_MARGIN),
+        Color::WHITE,
+    );
+}
@@ -0,0 +1,338 @@
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::{
+    input::{CursorPosition, OverUI},
+    prelude::*,
+    tabletop::{
+        grid::{current_grid, Grids},
+        polygon_mesh,
+        scenes::OffScene,
+        selection::token_rect,
+        tools::{tool_active, Tool},
+        TopdownCamera,
+    },
+};
+
+pub struct TemplatePlugin;
+impl Plugin for TemplatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TemplateTool>().add_systems(
+            Update,
+            (
+                (place_templates, edit_templates).run_if(tool_active(Tool::Template)),
+                (init_templates, update_templates).chain(),
+                update_template_targets,
+                draw_templates,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Height templates are drawn at, between the map and the tokens
+const TEMPLATE_LAYER: f32 = 12.0;
+
+/// Settings and preview of the template tool
+#[derive(Resource, Default)]
+pub struct TemplateTool {
+    pub shape: TemplateShape,
+    preview: Option<(Template, Option<Entity>)>,
+}
+
+/// Tokens that are inside of a template
+#[derive(Component, Debug, Default, Clone, PartialEq, Deref)]
+pub struct TemplateTargets(pub Vec<Entity>);
+
+impl Template {
+    pub fn direction(&self) -> Vec2 {
+        Vec2::from_angle(self.rotation)
+    }
+
+    /// Outline relative to the origin, facing to the right
+    pub fn local_outline(&self) -> Vec<Vec2> {
+        let size = self.size;
+        match self.shape {
+            TemplateShape::Circle => (0..48)
+                .map(|i| Vec2::from_angle(TAU * i as f32 / 48.0) * size)
+                .collect(),
+            TemplateShape::Cone => vec![
+                Vec2::ZERO,
+                Vec2::new(size, -size / 2.0),
+                Vec2::new(size, size / 2.0),
+            ],
+            TemplateShape::Square => vec![
+                Vec2::new(0.0, -size / 2.0),
+                Vec2::new(size, -size / 2.0),
+                Vec2::new(size, size / 2.0),
+                Vec2::new(0.0, size / 2.0),
+            ],
+            TemplateShape::Line => vec![
+                Vec2::new(0.0, -self.width / 2.0),
+                Vec2::new(size, -self.width / 2.0),
+                Vec2::new(size, self.width / 2.0),
+                Vec2::new(0.0, self.width / 2.0),
+            ],
+        }
+    }
+
+    pub fn outline(&self) -> Vec<Vec2> {
+        let direction = self.direction();
+        self.local_outline()
+            .into_iter()
+            .map(|point| direction.rotate(point) + self.origin)
+            .collect()
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let local = Vec2::from_angle(-self.rotation).rotate(point - self.origin);
+        let in_length = (0.0..=self.size).contains(&local.x);
+
+        match self.shape {
+            TemplateShape::Circle => local.length() <= self.size,
+            TemplateShape::Cone => in_length && local.y.abs() <= local.x / 2.0,
+            TemplateShape::Square => in_length && local.y.abs() <= self.size / 2.0,
+            TemplateShape::Line => in_length && local.y.abs() <= self.width / 2.0,
+        }
+    }
+}
+
+/// Tokens whose centers are covered by the template
+pub fn tokens_in_template<'a>(
+    template: &Template,
+    tokens: impl IntoIterator<Item = (Entity, &'a GlobalTransform)>,
+) -> Vec<Entity> {
+    tokens
+        .into_iter()
+        .filter(|(_, transform)| template.contains(transform.translation().xy()))
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+fn place_templates(
+    mut tool: ResMut<TemplateTool>,
+    tokens: Query<(Entity, &GlobalTransform), (With<Token>, Without<OffScene>)>,
+    grids: Grids,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    let grid = current_grid(&grids);
+    let cursor = cursor_pos.world_position;
+    let free = key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if mouse_input.just_pressed(MouseButton::Left) && !**over_ui {
+        // Templates started on a token stick to it, otherwise to the grid
+        let token = tokens
+            .iter()
+            .filter(|(_, transform)| token_rect(transform).contains(cursor))
+            .max_by(|a, b| a.1.translation().z.total_cmp(&b.1.translation().z));
+
+        let (origin, anchor) = match token {
+            Some((entity, transform)) => (transform.translation().xy(), Some(entity)),
+            None if free => (cursor, None),
+            None => (grid.snap_to_intersection(cursor), None),
+        };
+
+        let template = Template {
+            shape: tool.shape,
+            origin,
+            rotation: 0.0,
+            size: 0.0,
+            width: grid.cell_size,
+        };
+        tool.preview = Some((template, anchor));
+    }
+
+    let Some((mut template, anchor)) = tool.preview else {
+        return;
+    };
+
+    let offset = cursor - template.origin;
+    template.rotation = offset.y.atan2(offset.x);
+    template.size = if free {
+        offset.length()
+    } else {
+        (offset.length() / grid.cell_size).round() * grid.cell_size
+    };
+
+    if mouse_input.pressed(MouseButton::Left) {
+        tool.preview = Some((template, anchor));
+        return;
+    }
+
+    if template.size > 0.0 {
+        _ = connection
+            .send_message::<UnorderedReliable, _>(&TemplateMessage::Place(template, anchor));
+    }
+    tool.preview = None;
+}
+
+/// Right click removes a template, Q and E rotate it
+fn edit_templates(
+    templates: Query<(Entity, &Template), Without<OffScene>>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    egui: Query<&EguiContext>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    if **over_ui || egui.single().get().wants_keyboard_input() {
+        return;
+    }
+
+    let Some((entity, template)) = templates
+        .iter()
+        .find(|(_, template)| template.contains(cursor_pos.world_position))
+    else {
+        return;
+    };
+
+    if mouse_input.just_pressed(MouseButton::Right) {
+        _ = connection.send_message::<UnorderedReliable, _>(&TemplateMessage::Remove(entity));
+        return;
+    }
+
+    let rotation = if key_input.just_pressed(KeyCode::KeyQ) {
+        15f32.to_radians()
+    } else if key_input.just_pressed(KeyCode::KeyE) {
+        -15f32.to_radians()
+    } else {
+        return;
+    };
+
+    let template = Template {
+        rotation: template.rotation + rotation,
+        ..*template
+    };
+    _ = connection.send_message::<UnorderedReliable, _>(&TemplateMessage::Update(entity, template));
+}
+
+fn template_color(owner: Option<&Owner>, player_data: &PlayerData, alpha: f32) -> Color {
+    let color = owner
+        .and_then(|owner| player_data.get(&owner.0))
+        .map(|player| player.color)
+        .unwrap_or([255; 3]);
+    Color::rgba_u8(color[0], color[1], color[2], (alpha * 255.0) as u8)
+}
+
+fn init_templates(
+    mut commands: Commands,
+    templates: Query<(Entity, &Template, Option<&Owner>), Added<Template>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    player_data: Res<PlayerData>,
+) {
+    for (entity, template, owner) in templates.iter() {
+        let material = materials.add(StandardMaterial {
+            unlit: true,
+            base_color: template_color(owner, &player_data, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        });
+
+        commands.entity(entity).insert(PbrBundle {
+            mesh: meshes.add(polygon_mesh(&template.local_outline())),
+            material,
+            transform: Transform::from_translation(template.origin.extend(TEMPLATE_LAYER))
+                .with_rotation(Quat::from_rotation_z(template.rotation)),
+            ..default()
+        });
+    }
+}
+
+fn update_templates(
+    mut templates: Query<(&Template, &mut Transform, &mut Handle<Mesh>), Changed<Template>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (template, mut transform, mut mesh) in templates.iter_mut() {
+        *mesh = meshes.add(polygon_mesh(&template.local_outline()));
+        *transform = Transform::from_translation(template.origin.extend(TEMPLATE_LAYER))
+            .with_rotation(Quat::from_rotation_z(template.rotation));
+    }
+}
+
+fn update_template_targets(
+    mut commands: Commands,
+    templates: Query<(Entity, &Template, Option<&TemplateTargets>)>,
+    tokens: Query<(Entity, &GlobalTransform), (With<Token>, Without<OffScene>)>,
+) {
+    for (entity, template, targets) in templates.iter() {
+        let new_targets = TemplateTargets(tokens_in_template(template, tokens.iter()));
+
+        if targets != Some(&new_targets) {
+            commands.entity(entity).insert(new_targets);
+        }
+    }
+}
+
+fn draw_templates(
+    mut gizmos: Gizmos,
+    tool: Res<TemplateTool>,
+    templates: Query<(&Template, &TemplateTargets, Option<&Owner>), Without<OffScene>>,
+    tokens: Query<&GlobalTransform, With<Token>>,
+    camera: Query<(&Camera, &GlobalTransform), With<TopdownCamera>>,
+    egui: Query<&EguiContext>,
+    player_data: Res<PlayerData>,
+    player: Res<Player>,
+) {
+    if let Some((preview, _)) = tool.preview {
+        let color = Color::rgb_u8(player.color[0], player.color[1], player.color[2]);
+        let outline = preview.outline();
+        gizmos.linestrip(
+            outline
+                .iter()
+                .chain(outline.first())
+                .map(|point| point.extend(TEMPLATE_LAYER + 0.1)),
+            color,
+        );
+    }
+
+    let Ok((camera, camera_transform)) = camera.get_single() else {
+        return;
+    };
+
+    let painter = egui
+        .single()
+        .get()
+        .layer_painter(egui::LayerId::background());
+
+    for (template, targets, owner) in templates.iter() {
+        let color = template_color(owner, &player_data, 1.0);
+
+        for transform in targets.iter().filter_map(|entity| tokens.get(*entity).ok()) {
+            let rect = token_rect(transform);
+            gizmos.rect(
+                rect.center().extend(transform.translation().z + 0.1),
+                Quat::IDENTITY,
+                rect.size() * 0.9,
+                color,
+            );
+        }
+
+        if targets.is_empty() {
+            continue;
+        }
+
+        let Some(label_pos) =
+            camera.world_to_viewport(camera_transform, template.origin.extend(TEMPLATE_LAYER))
+        else {
+            continue;
+        };
+
+        let [r, g, b, _] = color.as_rgba_u8();
+        painter.text(
+            egui::pos2(label_pos.x, label_pos.y),
+            egui::Align2::CENTER_BOTTOM,
+            format!("{} targets", targets.len()),
+            egui::FontId::proportional(16.0),
+            egui::Color32::from_rgb(r, g, b),
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use bevy_egui::EguiContext;
+
+use crate::{
+    networking::client::{local_role, ClientId},
+    prelude::*,
+};
+
+pub struct ToolsPlugin;
+impl Plugin for ToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tool>()
+            .register_type::<Tool>()
+            .add_systems(PreUpdate, switch_tools);
+    }
+}
+
+/// What left mouse button does on the tabletop
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    #[default]
+    Select,
+    Ruler,
+    Template,
+    Draw,
+    Fog,
+    Walls,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 6] = [
+        Tool::Select,
+        Tool::Ruler,
+        Tool::Template,
+        Tool::Draw,
+        Tool::Fog,
+        Tool::Walls,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Select => "Select",
+            Tool::Ruler => "Ruler",
+            Tool::Template => "Template",
+            Tool::Draw => "Draw",
+            Tool::Fog => "Fog",
+            Tool::Walls => "Walls",
+        }
+    }
+
+    pub fn shortcut(self) -> KeyCode {
+        match self {
+            Tool::Select => KeyCode::KeyV,
+            Tool::Ruler => KeyCode::KeyR,
+            Tool::Template => KeyCode::KeyT,
+            Tool::Draw => KeyCode::KeyD,
+            Tool::Fog => KeyCode::KeyF,
+            Tool::Walls => KeyCode::KeyW,
+        }
+    }
+
+    /// Tools that change the table for everyone
+    pub fn gm_only(self) -> bool {
+        matches!(self, Tool::Fog | Tool::Walls)
+    }
+}
+
+/// Run condition for systems that only work with a specific tool
+pub fn tool_active(tool: Tool) -> impl Fn(Res<Tool>) -> bool + Clone {
+    move |active: Res<Tool>| *active == tool
+}
+
+fn switch_tools(
+    mut tool: ResMut<Tool>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    egui: Query<&EguiContext>,
+    client_id: Res<ClientId>,
+    roles: Res<PlayerRoles>,
+    server_state: Res<State<lightyear::prelude::server::NetworkingState>>,
+) {
+    let is_gm = local_role(&client_id, &roles, &server_state) == Role::Gm;
+
+    // Losing the GM role takes GM tools away
+    if !is_gm && tool.gm_only() {
+        *tool = Tool::Select;
+    }
+
+    if egui.single().get().wants_keyboard_input() {
+        return;
+    }
+
+    if let Some(new_tool) = Tool::ALL
+        .into_iter()
+        .filter(|tool| is_gm || !tool.gm_only())
+        .find(|tool| key_input.just_pressed(tool.shortcut()))
+    {
+        *tool = new_tool;
+    }
+}
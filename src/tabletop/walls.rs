@@ -0,0 +1,258 @@
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+
+use crate::{
+    input::{CursorPosition, OverUI},
+    networking::{
+        client::{local_player_is_gm, local_role, ClientId},
+        vision::distance_to_segment,
+    },
+    prelude::*,
+    tabletop::{
+        grid::{current_grid, Grids},
+        scenes::OffScene,
+        tools::{tool_active, Tool},
+    },
+};
+
+pub struct WallPlugin;
+impl Plugin for WallPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WallTool>().add_systems(
+            Update,
+            (
+                (place_walls, edit_walls)
+                    .run_if(tool_active(Tool::Walls).and_then(local_player_is_gm)),
+                toggle_doors.run_if(tool_active(Tool::Select)),
+                draw_walls,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Height walls are drawn at, above the tokens but under the fog
+const WALL_LAYER: f32 = 25.0;
+
+/// How close to a wall the cursor has to be to interact with it
+const WALL_PICK_DISTANCE: f32 = 0.25;
+
+/// Settings and preview of the wall tool
+#[derive(Resource, Default)]
+pub struct WallTool {
+    pub kind: WallKind,
+    start: Option<Vec2>,
+}
+
+impl WallKind {
+    /// Kinds that can be drawn with the wall tool
+    pub const ALL: [WallKind; 3] = [
+        WallKind::Wall,
+        WallKind::Door(DoorState::Closed),
+        WallKind::Window,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WallKind::Wall => "Wall",
+            WallKind::Door(_) => "Door",
+            WallKind::Window => "Window",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            WallKind::Wall => Color::rgb_u8(230, 230, 230),
+            WallKind::Door(DoorState::Open) => Color::rgb_u8(90, 200, 90),
+            WallKind::Door(DoorState::Closed) => Color::rgb_u8(200, 140, 60),
+            WallKind::Door(DoorState::Locked) => Color::rgb_u8(220, 60, 60),
+            WallKind::Window => Color::rgb_u8(120, 190, 240),
+        }
+    }
+}
+
+/// Wall closest to the point, if it's close enough to pick
+fn wall_under_cursor<'a>(
+    walls: impl IntoIterator<Item = (Entity, &'a Wall)>,
+    point: Vec2,
+) -> Option<(Entity, &'a Wall)> {
+    walls
+        .into_iter()
+        .map(|(entity, wall)| {
+            (
+                entity,
+                wall,
+                distance_to_segment(point, wall.start, wall.end),
+            )
+        })
+        .filter(|(_, _, distance)| *distance <= WALL_PICK_DISTANCE)
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(entity, wall, _)| (entity, wall))
+}
+
+/// Walls are dragged out from one grid corner to another. Hold Shift to place them freely
+fn place_walls(
+    mut tool: ResMut<WallTool>,
+    grids: Grids,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    let point = if key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        cursor_pos.world_position
+    } else {
+        current_grid(&grids).snap_to_intersection(cursor_pos.world_position)
+    };
+
+    if mouse_input.just_pressed(MouseButton::Left) && !**over_ui {
+        tool.start = Some(point);
+    }
+
+    let Some(start) = tool.start else {
+        return;
+    };
+
+    if mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+
+    if start.distance(point) > f32::EPSILON {
+        let wall = Wall {
+            start,
+            end: point,
+            kind: tool.kind,
+        };
+        _ = connection.send_message::<UnorderedReliable, _>(&WallMessage::Place(wall));
+    }
+    tool.start = None;
+}
+
+/// Right click removes a wall, L locks and unlocks a door
+fn edit_walls(
+    walls: Query<(Entity, &Wall), Without<OffScene>>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    egui: Query<&EguiContext>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    if **over_ui || egui.single().get().wants_keyboard_input() {
+        return;
+    }
+
+    let Some((entity, wall)) = wall_under_cursor(walls.iter(), cursor_pos.world_position) else {
+        return;
+    };
+
+    if mouse_input.just_pressed(MouseButton::Right) {
+        _ = connection.send_message::<UnorderedReliable, _>(&WallMessage::Remove(entity));
+        return;
+    }
+
+    if let WallKind::Door(state) = wall.kind {
+        if key_input.just_pressed(KeyCode::KeyL) {
+            let state = match state {
+                DoorState::Locked => DoorState::Closed,
+                DoorState::Open | DoorState::Closed => DoorState::Locked,
+            };
+            _ = connection
+                .send_message::<UnorderedReliable, _>(&WallMessage::SetDoor(entity, state));
+        }
+    }
+}
+
+/// Right clicking a door opens or closes it
+fn toggle_doors(
+    walls: Query<(Entity, &Wall), Without<OffScene>>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Right) || **over_ui {
+        return;
+    }
+
+    let doors = walls
+        .iter()
+        .filter(|(_, wall)| matches!(wall.kind, WallKind::Door(_)));
+
+    let Some((entity, wall)) = wall_under_cursor(doors, cursor_pos.world_position) else {
+        return;
+    };
+
+    let state = match wall.kind {
+        WallKind::Door(DoorState::Open) => DoorState::Closed,
+        WallKind::Door(DoorState::Closed) => DoorState::Open,
+        _ => {
+            info!("The door is locked");
+            return;
+        }
+    };
+
+    _ = connection.send_message::<UnorderedReliable, _>(&WallMessage::SetDoor(entity, state));
+}
+
+/// GM sees every wall, players only see doors
+fn draw_walls(
+    mut gizmos: Gizmos,
+    mut tool: ResMut<WallTool>,
+    walls: Query<&Wall, Without<OffScene>>,
+    grids: Grids,
+    active_tool: Res<Tool>,
+    cursor_pos: Res<CursorPosition>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    client_id: Res<ClientId>,
+    roles: Res<PlayerRoles>,
+    server_state: Res<State<server::NetworkingState>>,
+) {
+    let is_gm = local_role(&client_id, &roles, &server_state) == Role::Gm;
+
+    for wall in walls.iter() {
+        if !is_gm && !matches!(wall.kind, WallKind::Door(_)) {
+            continue;
+        }
+
+        gizmos.line(
+            wall.start.extend(WALL_LAYER),
+            wall.end.extend(WALL_LAYER),
+            wall.kind.color(),
+        );
+
+        // Doors get a handle in the middle, so they're easier to spot
+        if let WallKind::Door(_) = wall.kind {
+            gizmos.circle(
+                wall.start.lerp(wall.end, 0.5).extend(WALL_LAYER),
+                Direction3d::Z,
+                0.1,
+                wall.kind.color(),
+            );
+        }
+    }
+
+    if *active_tool != Tool::Walls {
+        if tool.start.is_some() {
+            tool.start = None;
+        }
+        return;
+    }
+
+    let Some(start) = tool.start else {
+        return;
+    };
+
+    let end = if key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        cursor_pos.world_position
+    } else {
+        current_grid(&grids).snap_to_intersection(cursor_pos.world_position)
+    };
+
+    gizmos.line(
+        start.extend(WALL_LAYER),
+        end.extend(WALL_LAYER),
+        tool.kind.color(),
+    );
+}
@@ -4,11 +4,18 @@ use std::{
     sync::Mutex,
 };
 
-use bevy::{ecs::event::ManualEventReader, utils::HashMap};
+use bevy::{
+    ecs::event::ManualEventReader,
+    utils::{HashMap, HashSet},
+};
 use client::{Authentication, ClientConfig, ClientTransport, ConnectionManager};
 use lightyear::prelude::*;
 
-use crate::{networking::shared::DEFAULT_PORT, prelude::*};
+use crate::{
+    networking::{session::SessionRequest, shared::DEFAULT_PORT},
+    prelude::*,
+    tabletop::{maps::ImportMap, selection::Selected},
+};
 
 #[derive(Event, Debug, Default, Deref, DerefMut, Clone)]
 pub struct RawTerminalCommand(String);
@@ -46,6 +53,14 @@ impl Plugin for TerminalPlugin {
             .init_command::<HostCommand>()
             .init_command::<SendCommand>()
             .init_command::<ConnectCommand>()
+            .init_command::<OwnersCommand>()
+            .init_command::<RoleCommand>()
+            .init_command::<SightCommand>()
+            .init_command::<MapCommand>()
+            .init_command::<SaveCommand>()
+            .init_command::<LoadCommand>()
+            .init_command::<UndoCommand>()
+            .init_command::<RedoCommand>()
             .add_systems(Startup, spawn_stdin_reader)
             .add_systems(PreUpdate, (send_raw_event, process_raw_events));
     }
@@ -91,6 +106,11 @@ pub trait Command: Send + Sync {
 
     fn help_string(&self) -> &'static str;
 
+    /// Permission local player needs to run this command. Server checks it again anyway
+    fn permission(&self) -> Option<Permission> {
+        None
+    }
+
     fn run_command(&mut self, args: &str, world: &mut World);
 }
 
@@ -214,6 +234,309 @@ impl Command for ConnectCommand {
     }
 }
 
+#[derive(Default)]
+struct OwnersCommand;
+
+impl Command for OwnersCommand {
+    fn run_command(&mut self, args: &str, world: &mut World) {
+        let mut owners = HashSet::default();
+
+        for player in args.split_whitespace() {
+            let client_id = player.parse::<u64>().ok().or_else(|| {
+                world.get_resource::<PlayerData>().and_then(|player_data| {
+                    player_data
+                        .iter()
+                        .find(|(_, data)| data.name == player)
+                        .map(|(id, _)| *id)
+                })
+            });
+
+            let Some(client_id) = client_id else {
+                error!("Player \"{player}\" not found");
+                return;
+            };
+
+            owners.insert(client_id);
+        }
+
+        let selected = world
+            .query_filtered::<Entity, With<Selected>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+
+        if selected.is_empty() {
+            error!("No tokens selected");
+            return;
+        }
+
+        let mut connection = world.resource_mut::<ConnectionManager>();
+        for entity in selected {
+            let message = EditTokenMessage {
+                entity,
+                edit: TokenEdit::SetOwners(owners.clone()),
+            };
+            _ = connection.send_message::<UnorderedReliable, _>(&message);
+        }
+    }
+
+    fn stem(&self) -> &'static str {
+        "owners"
+    }
+
+    fn help_string(&self) -> &'static str {
+        "Gives selected tokens to listed players (by name or ID). No players makes them GM only"
+    }
+
+    fn permission(&self) -> Option<Permission> {
+        Some(Permission::ManageTable)
+    }
+}
+
+#[derive(Default)]
+struct SightCommand;
+
+impl Command for SightCommand {
+    fn run_command(&mut self, args: &str, world: &mut World) {
+        let Ok(radius) = args.trim().parse::<f32>() else {
+            error!("Expected sight radius, got \"{args}\"");
+            return;
+        };
+
+        let selected = world
+            .query_filtered::<Entity, With<Selected>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+
+        if selected.is_empty() {
+            error!("No tokens selected");
+            return;
+        }
+
+        let mut connection = world.resource_mut::<ConnectionManager>();
+        for entity in selected {
+            let message = EditTokenMessage {
+                entity,
+                edit: TokenEdit::SetSight(radius),
+            };
+            _ = connection.send_message::<UnorderedReliable, _>(&message);
+        }
+    }
+
+    fn stem(&self) -> &'static str {
+        "sight"
+    }
+
+    fn help_string(&self) -> &'static str {
+        "Sets how far selected tokens can see when token vision is enabled. 0 makes them blind"
+    }
+
+    fn permission(&self) -> Option<Permission> {
+        Some(Permission::ManageTable)
+    }
+}
+
+#[derive(Default)]
+struct MapCommand;
+
+impl Command for MapCommand {
+    fn run_command(&mut self, args: &str, world: &mut World) {
+        if !matches!(
+            world.resource::<State<server::NetworkingState>>().get(),
+            server::NetworkingState::Started
+        ) {
+            error!("Only the host can import maps");
+            return;
+        }
+
+        let path = args.trim().trim_matches('"');
+        if path.is_empty() {
+            error!("Usage: map <path>");
+            return;
+        }
+
+        world.send_event(ImportMap {
+            path: path.into(),
+            position: Vec2::ZERO,
+        });
+    }
+
+    fn stem(&self) -> &'static str {
+        "map"
+    }
+
+    fn help_string(&self) -> &'static str {
+        "Puts an image from disk on the table as a new map. Dropping the file onto the window works too"
+    }
+
+    fn permission(&self) -> Option<Permission> {
+        Some(Permission::ManageTable)
+    }
+}
+
+#[derive(Default)]
+struct SaveCommand;
+
+impl Command for SaveCommand {
+    fn run_command(&mut self, args: &str, world: &mut World) {
+        if !matches!(
+            world.resource::<State<server::NetworkingState>>().get(),
+            server::NetworkingState::Started
+        ) {
+            error!("Only the host can save the session");
+            return;
+        }
+
+        let name = args.trim().trim_matches('"');
+        if name.is_empty() {
+            error!("Usage: save <name>");
+            return;
+        }
+
+        world.send_event(SessionRequest::Save(name.to_string()));
+    }
+
+    fn stem(&self) -> &'static str {
+        "save"
+    }
+
+    fn help_string(&self) -> &'static str {
+        "Saves the whole session, assets included, under the given name"
+    }
+
+    fn permission(&self) -> Option<Permission> {
+        Some(Permission::ManageTable)
+    }
+}
+
+#[derive(Default)]
+struct LoadCommand;
+
+impl Command for LoadCommand {
+    fn run_command(&mut self, args: &str, world: &mut World) {
+        if !matches!(
+            world.resource::<State<server::NetworkingState>>().get(),
+            server::NetworkingState::Started
+        ) {
+            error!("Only the host can load a session");
+            return;
+        }
+
+        let name = args.trim().trim_matches('"');
+        if name.is_empty() {
+            error!("Usage: load <name>");
+            return;
+        }
+
+        world.send_event(SessionRequest::Load(name.to_string()));
+    }
+
+    fn stem(&self) -> &'static str {
+        "load"
+    }
+
+    fn help_string(&self) -> &'static str {
+        "Replaces the table with a session saved earlier"
+    }
+
+    fn permission(&self) -> Option<Permission> {
+        Some(Permission::ManageTable)
+    }
+}
+
+#[derive(Default)]
+struct UndoCommand;
+
+impl Command for UndoCommand {
+    fn run_command(&mut self, _args: &str, world: &mut World) {
+        let mut connection = world.resource_mut::<ConnectionManager>();
+        if let Err(err) = connection.send_message::<UnorderedReliable, _>(&HistoryMessage::Undo) {
+            error!("Failed to send undo request: {err}");
+        }
+    }
+
+    fn stem(&self) -> &'static str {
+        "undo"
+    }
+
+    fn help_string(&self) -> &'static str {
+        "Reverts your last edit of the table"
+    }
+}
+
+#[derive(Default)]
+struct RedoCommand;
+
+impl Command for RedoCommand {
+    fn run_command(&mut self, _args: &str, world: &mut World) {
+        let mut connection = world.resource_mut::<ConnectionManager>();
+        if let Err(err) = connection.send_message::<UnorderedReliable, _>(&HistoryMessage::Redo) {
+            error!("Failed to send redo request: {err}");
+        }
+    }
+
+    fn stem(&self) -> &'static str {
+        "redo"
+    }
+
+    fn help_string(&self) -> &'static str {
+        "Makes the last undone edit again"
+    }
+}
+
+#[derive(Default)]
+struct RoleCommand;
+
+impl Command for RoleCommand {
+    fn run_command(&mut self, args: &str, world: &mut World) {
+        if !matches!(
+            world.resource::<State<server::NetworkingState>>().get(),
+            server::NetworkingState::Started
+        ) {
+            error!("Only the host can assign roles");
+            return;
+        }
+
+        let Some((player, role)) = args.rsplit_once(' ') else {
+            error!("Usage: role <player> <gm|player|spectator>");
+            return;
+        };
+
+        let Ok(role) = role.trim().parse::<Role>() else {
+            error!("Unknown role \"{}\"", role.trim());
+            return;
+        };
+
+        let player = player.trim();
+        let client_id = player.parse::<u64>().ok().or_else(|| {
+            world
+                .resource::<PlayerData>()
+                .iter()
+                .find(|(_, data)| data.name == player)
+                .map(|(id, _)| *id)
+        });
+
+        let Some(client_id) = client_id else {
+            error!("Player \"{player}\" not found");
+            return;
+        };
+
+        world.resource_mut::<PlayerRoles>().insert(client_id, role);
+        info!("{player} is now {}", role.name());
+    }
+
+    fn stem(&self) -> &'static str {
+        "role"
+    }
+
+    fn help_string(&self) -> &'static str {
+        "Assigns a role to a player: role <player> <gm|player|spectator>"
+    }
+
+    fn permission(&self) -> Option<Permission> {
+        Some(Permission::ManageTable)
+    }
+}
+
 fn process_raw_events(
     world: &mut World,
     mut ev_reader: Local<ManualEventReader<RawTerminalCommand>>,
@@ -236,6 +559,24 @@ fn process_raw_events(
                 return;
             };
 
+            if let Some(permission) = command.permission() {
+                let role = world
+                    .get_resource::<crate::networking::client::ClientId>()
+                    .zip(world.get_resource::<PlayerRoles>())
+                    .map(|(client_id, roles)| roles.role(client_id.0))
+                    .unwrap_or_default();
+
+                let is_host = matches!(
+                    world.resource::<State<server::NetworkingState>>().get(),
+                    server::NetworkingState::Started
+                );
+
+                if !is_host && !role.allows(permission) {
+                    error!("{} can't use \"{}\"", role.name(), command.stem());
+                    continue;
+                }
+            }
+
             command.run_command(args.trim(), world);
         }
     });
@@ -15,9 +15,6 @@ impl Plugin for ChatWindowPlugin {
     }
 }
 
-#[derive(Resource, Deref, DerefMut, Default)]
-pub struct ChatHistory(pub Vec<ChatMessage>);
-
 #[derive(Component, Debug, Default, Clone)]
 pub struct ChatWindow {
     pub input: String,
@@ -85,6 +82,12 @@ fn display_window(
                                     format!("{} left the game", player.name,),
                                 );
                             }
+                            ChatMessage::Turn { round, name } => {
+                                ui.colored_label(
+                                    Color32::LIGHT_BLUE,
+                                    format!("Round {round}: {name}'s turn"),
+                                );
+                            }
                         }
                     }
                 },
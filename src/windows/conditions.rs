@@ -0,0 +1,87 @@
+use crate::{networking::client::local_player_is_gm, prelude::*};
+use bevy_egui::EguiContext;
+use lightyear::prelude::client::*;
+
+pub struct ConditionsWindowPlugin;
+impl Plugin for ConditionsWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_window.run_if(local_player_is_gm));
+
+        // Create window
+        app.world
+            .spawn((Name::new("Conditions Window"), ConditionsWindow::default()));
+    }
+}
+
+/// GM's editor of the condition catalogue
+#[derive(Component, Debug, Default, Clone)]
+pub struct ConditionsWindow {
+    /// Unsaved changes, so typing isn't interrupted by the catalogue replicating back
+    draft: Option<ConditionCatalogue>,
+}
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    mut conditions_window: Query<(Entity, &mut ConditionsWindow)>,
+    catalogue: Res<ConditionCatalogue>,
+    mut connection: ResMut<ConnectionManager>,
+) {
+    let (entity, mut conditions_window) = conditions_window.single_mut();
+    let mut egui_context = egui_context.single_mut();
+
+    let mut draft = conditions_window
+        .draft
+        .clone()
+        .unwrap_or_else(|| catalogue.clone());
+
+    let window = egui::Window::new("Conditions")
+        .id(egui::Id::new(entity))
+        .default_open(false)
+        .collapsible(true);
+
+    let mut save = false;
+    let mut revert = false;
+
+    window.show(egui_context.get_mut(), |ui| {
+        let mut removed = None;
+
+        egui::Grid::new("Condition catalogue").show(ui, |ui| {
+            for (i, condition) in draft.iter_mut().enumerate() {
+                ui.color_edit_button_srgb(&mut condition.color);
+                ui.add(
+                    egui::TextEdit::singleline(&mut condition.icon)
+                        .char_limit(2)
+                        .desired_width(24.0),
+                );
+                ui.add(egui::TextEdit::singleline(&mut condition.label).desired_width(120.0));
+                if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                    removed = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = removed {
+            draft.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Add").clicked() {
+                draft.push(Condition::new("New condition", "?", [200, 200, 200]));
+            }
+
+            let changed = draft != *catalogue;
+            save = ui.add_enabled(changed, egui::Button::new("Save")).clicked();
+            revert = ui
+                .add_enabled(changed, egui::Button::new("Revert"))
+                .clicked();
+        });
+    });
+
+    if save {
+        _ = connection
+            .send_message::<UnorderedReliable, _>(&SetConditionCatalogueMessage(draft.clone()));
+    }
+
+    conditions_window.draft = (!save && !revert && draft != *catalogue).then_some(draft);
+}
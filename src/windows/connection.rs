@@ -35,7 +35,10 @@ fn display_window(
     mut connection: ResMut<client::ConnectionManager>,
     mut client_config: ResMut<client::ClientConfig>,
     mut player: ResMut<Player>,
+    mut roles: ResMut<PlayerRoles>,
     mut commands: Commands,
+    player_data: Res<PlayerData>,
+    clients: Res<ConnectedClients>,
     client_id: Option<Res<crate::networking::client::ClientId>>,
     server_state: Res<State<server::NetworkingState>>,
     client_state: Res<State<client::NetworkingState>>,
@@ -161,6 +164,40 @@ fn display_window(
             }
         });
 
+        let is_host = matches!(server_state.get(), server::NetworkingState::Started);
+        let local_id = client_id.as_ref().map(|id| id.0);
+
+        if is_host && clients.iter().any(|client| Some(*client) != local_id) {
+            ui.separator();
+
+            egui::Grid::new("Player roles").show(ui, |ui| {
+                for client in clients.iter().filter(|client| Some(**client) != local_id) {
+                    let name = player_data
+                        .get(client)
+                        .map(|player| player.name.as_str())
+                        .unwrap_or("Player");
+
+                    // Only touch the resource on change, every write gets replicated
+                    let current_role = roles.role(*client);
+                    let mut role = current_role;
+
+                    ui.label(name);
+                    egui::ComboBox::from_id_source(client)
+                        .selected_text(role.name())
+                        .show_ui(ui, |ui| {
+                            for option in Role::ALL {
+                                ui.selectable_value(&mut role, option, option.name());
+                            }
+                        });
+                    ui.end_row();
+
+                    if role != current_role {
+                        roles.insert(*client, role);
+                    }
+                }
+            });
+        }
+
         if !connection_window.error.is_empty() {
             ui.colored_label(Color32::RED, &*connection_window.error);
         }
@@ -0,0 +1,126 @@
+use crate::{networking::client::local_player_is_gm, prelude::*, tabletop::grid::Grids};
+use bevy_egui::EguiContext;
+use lightyear::prelude::client::*;
+
+pub struct GridWindowPlugin;
+impl Plugin for GridWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_window.run_if(local_player_is_gm));
+
+        // Create window
+        app.world.spawn((Name::new("Grid Window"), GridWindow));
+    }
+}
+
+#[derive(Component, Debug, Default, Clone)]
+pub struct GridWindow;
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    grid_window: Query<Entity, With<GridWindow>>,
+    grids: Grids,
+    mut connection: ResMut<ConnectionManager>,
+) {
+    let Some(current) = grids.iter().next().cloned() else {
+        return;
+    };
+
+    let entity = grid_window.single();
+    let mut egui_context = egui_context.single_mut();
+    let mut settings = current.clone();
+
+    let window = egui::Window::new("Grid")
+        .id(egui::Id::new(entity))
+        .default_open(false)
+        .collapsible(true);
+
+    window.show(egui_context.get_mut(), |ui| {
+        egui::Grid::new("Grid settings").show(ui, |ui| {
+            ui.label("Type");
+            egui::ComboBox::from_id_source("Grid type")
+                .selected_text(grid_kind_name(settings.kind))
+                .show_ui(ui, |ui| {
+                    for kind in [
+                        GridKind::Square,
+                        GridKind::HexPointy,
+                        GridKind::HexFlat,
+                        GridKind::Gridless,
+                    ] {
+                        ui.selectable_value(&mut settings.kind, kind, grid_kind_name(kind));
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Cell size");
+            ui.add(
+                egui::DragValue::new(&mut settings.cell_size)
+                    .speed(0.01)
+                    .clamp_range(0.1..=10.0),
+            );
+            ui.end_row();
+
+            ui.label("Origin");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut settings.origin.x).speed(0.01));
+                ui.add(egui::DragValue::new(&mut settings.origin.y).speed(0.01));
+            });
+            ui.end_row();
+
+            ui.label("Snap to");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut settings.snap, SnapMode::Centers, "Centers");
+                ui.selectable_value(&mut settings.snap, SnapMode::Intersections, "Intersections");
+                ui.selectable_value(&mut settings.snap, SnapMode::Disabled, "Nothing");
+            });
+            ui.end_row();
+
+            ui.label("Distance");
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut settings.cell_distance)
+                        .speed(0.1)
+                        .clamp_range(0.0..=1000.0),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut settings.distance_unit)
+                        .char_limit(8)
+                        .desired_width(40.0),
+                );
+                ui.label("per cell");
+            });
+            ui.end_row();
+
+            ui.label("Diagonals");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut settings.diagonals, DiagonalRule::Chebyshev, "1-1-1");
+                ui.selectable_value(&mut settings.diagonals, DiagonalRule::Alternating, "1-2-1");
+                ui.selectable_value(
+                    &mut settings.diagonals,
+                    DiagonalRule::Euclidean,
+                    "Euclidean",
+                );
+            });
+            ui.end_row();
+
+            ui.label("Lines");
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgb(&mut settings.color);
+                ui.add(egui::Slider::new(&mut settings.opacity, 0.0..=1.0).text("opacity"));
+            });
+            ui.end_row();
+        });
+    });
+
+    if settings != current {
+        _ = connection.send_message::<UnorderedReliable, _>(&SetGridMessage(settings));
+    }
+}
+
+fn grid_kind_name(kind: GridKind) -> &'static str {
+    match kind {
+        GridKind::Square => "Square",
+        GridKind::HexPointy => "Hex (pointy)",
+        GridKind::HexFlat => "Hex (flat)",
+        GridKind::Gridless => "Gridless",
+    }
+}
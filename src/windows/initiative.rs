@@ -0,0 +1,167 @@
+use crate::{
+    networking::client::{local_role, ClientId},
+    prelude::*,
+    tabletop::{scenes::OffScene, selection::Selected},
+};
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+
+pub struct InitiativeWindowPlugin;
+impl Plugin for InitiativeWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_window);
+
+        // Create window
+        app.world
+            .spawn((Name::new("Initiative Window"), InitiativeWindow));
+    }
+}
+
+/// Turn order tracker, players can manage their own tokens
+#[derive(Component, Debug, Default, Clone)]
+pub struct InitiativeWindow;
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    initiative_window: Query<Entity, With<InitiativeWindow>>,
+    trackers: Query<&Initiative, Without<OffScene>>,
+    tokens: Query<Option<&TokenOwners>, With<Token>>,
+    selected: Query<Entity, (With<Selected>, With<Token>)>,
+    client_id: Res<ClientId>,
+    roles: Res<PlayerRoles>,
+    server_state: Res<State<server::NetworkingState>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    let Some(initiative) = trackers.iter().next() else {
+        return;
+    };
+
+    let entity = initiative_window.single();
+    let mut egui_context = egui_context.single_mut();
+
+    let role = local_role(&client_id, &roles, &server_state);
+    let is_gm = role == Role::Gm;
+    let controls = |token: Entity| {
+        tokens
+            .get(token)
+            .is_ok_and(|owners| role.can_control(client_id.0, Permission::EditTokens, owners))
+    };
+
+    let selected = selected
+        .iter()
+        .filter(|token| controls(*token))
+        .collect::<Vec<_>>();
+
+    let mut messages = Vec::new();
+
+    let window = egui::Window::new("Initiative")
+        .id(egui::Id::new(entity))
+        .default_open(false)
+        .collapsible(true);
+
+    window.show(egui_context.get_mut(), |ui| {
+        if initiative.round > 0 {
+            ui.label(format!("Round {}", initiative.round));
+        } else {
+            ui.label("Combat hasn't started");
+        }
+
+        egui::Grid::new("Initiative entries").show(ui, |ui| {
+            for (i, entry) in initiative.entries.iter().enumerate() {
+                let active = initiative.round > 0 && i == initiative.turn;
+                let editable = controls(entry.token);
+
+                ui.label(if active { "▶" } else { "" });
+                ui.label(egui::RichText::new(&entry.name).strong().color(if active {
+                    egui::Color32::from_rgb(255, 204, 51)
+                } else {
+                    ui.visuals().text_color()
+                }));
+
+                let mut value = entry.initiative.unwrap_or_default();
+                let has_value = entry.initiative.is_some();
+                let response = ui.add_enabled(
+                    editable,
+                    egui::DragValue::new(&mut value)
+                        .speed(0.1)
+                        .custom_formatter(move |n, _| {
+                            if has_value {
+                                format!("{n}")
+                            } else {
+                                String::from("-")
+                            }
+                        }),
+                );
+                if response.changed() {
+                    messages.push(InitiativeMessage::Set(entry.token, value));
+                }
+
+                if editable {
+                    if ui.small_button("🎲").on_hover_text("Roll d20").clicked() {
+                        messages.push(InitiativeMessage::Roll(vec![entry.token]));
+                    }
+                    if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                        messages.push(InitiativeMessage::Remove(entry.token));
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let add = ui.add_enabled(!selected.is_empty(), egui::Button::new("Add selected"));
+            if add.clicked() {
+                messages.push(InitiativeMessage::Add(selected.clone()));
+            }
+
+            let unrolled = initiative
+                .entries
+                .iter()
+                .filter(|entry| entry.initiative.is_none() && controls(entry.token))
+                .map(|entry| entry.token)
+                .collect::<Vec<_>>();
+
+            let roll = ui.add_enabled(!unrolled.is_empty(), egui::Button::new("Roll missing"));
+            if roll.clicked() {
+                messages.push(InitiativeMessage::Roll(unrolled));
+            }
+        });
+
+        let own_turn = initiative
+            .active()
+            .is_some_and(|active| controls(active.token));
+
+        ui.horizontal(|ui| {
+            if is_gm {
+                if ui.button("Sort").clicked() {
+                    messages.push(InitiativeMessage::Sort);
+                }
+
+                if initiative.round == 0 {
+                    let start =
+                        ui.add_enabled(!initiative.entries.is_empty(), egui::Button::new("Start"));
+                    if start.clicked() {
+                        messages.push(InitiativeMessage::Start);
+                    }
+                } else {
+                    if ui.button("⏮ Previous").clicked() {
+                        messages.push(InitiativeMessage::Previous);
+                    }
+                    if ui.button("Next ⏭").clicked() {
+                        messages.push(InitiativeMessage::Next);
+                    }
+                }
+
+                if ui.button("End").clicked() {
+                    messages.push(InitiativeMessage::End);
+                }
+            } else if own_turn && ui.button("End turn").clicked() {
+                messages.push(InitiativeMessage::Next);
+            }
+        });
+    });
+
+    for message in messages {
+        _ = connection.send_message::<UnorderedReliable, _>(&message);
+    }
+}
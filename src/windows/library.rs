@@ -0,0 +1,147 @@
+use crate::{
+    input::{CursorPosition, OverUI},
+    networking::library::{LibraryRequest, TokenLibrary},
+    prelude::*,
+    tabletop::{grid::Grids, spawning::placement_position},
+    windows::Thumbnails,
+};
+use bevy_egui::{EguiContext, EguiUserTextures};
+use lightyear::prelude::*;
+
+pub struct LibraryWindowPlugin;
+impl Plugin for LibraryWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            display_window.run_if(in_state(server::NetworkingState::Started)),
+        );
+
+        // Create window
+        app.world
+            .spawn((Name::new("Library Window"), LibraryWindow::default()));
+    }
+}
+
+/// Host's token library, entries are dragged from it onto the table
+#[derive(Component, Debug, Default, Clone)]
+pub struct LibraryWindow {
+    search: String,
+    /// Entry being dragged, placed where the mouse is let go
+    dragging: Option<Uuid>,
+    thumbnails: Thumbnails,
+}
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    mut library_window: Query<(Entity, &mut LibraryWindow)>,
+    library: Res<TokenLibrary>,
+    mut user_textures: ResMut<EguiUserTextures>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    cursor_pos: Res<CursorPosition>,
+    over_ui: Res<OverUI>,
+    grids: Grids,
+    mut requests: EventWriter<LibraryRequest>,
+) {
+    let (entity, mut library_window) = library_window.single_mut();
+    let library_window = library_window.as_mut();
+    let mut egui_context = egui_context.single_mut();
+
+    library_window.thumbnails.update(
+        &mut user_textures,
+        library
+            .entries
+            .iter()
+            .map(|entry| (entry.id, entry.image.clone())),
+    );
+
+    let window = egui::Window::new("Library")
+        .id(egui::Id::new(entity))
+        .default_open(false)
+        .collapsible(true);
+
+    window.show(egui_context.get_mut(), |ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut library_window.search)
+                .hint_text("Search")
+                .desired_width(180.0),
+        );
+        ui.separator();
+
+        if library.entries.is_empty() {
+            ui.weak("Add tokens to the library from their menu");
+        }
+
+        let search = library_window.search.trim().to_lowercase();
+        let entries = library
+            .entries
+            .iter()
+            .filter(|entry| entry.template.name.to_lowercase().contains(&search));
+
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for entry in entries {
+                    let Some(texture) = library_window.thumbnails.get(entry.id) else {
+                        continue;
+                    };
+
+                    ui.horizontal(|ui| {
+                        let thumbnail = egui::load::SizedTexture::new(texture, [40.0, 40.0]);
+
+                        let response = ui
+                            .add(
+                                egui::ImageButton::new(thumbnail)
+                                    .sense(egui::Sense::drag())
+                                    .selected(library_window.dragging == Some(entry.id)),
+                            )
+                            .on_hover_text("Drag onto the table, hold Shift to place off the grid");
+
+                        if response.drag_started() {
+                            library_window.dragging = Some(entry.id);
+                        }
+
+                        ui.label(&entry.template.name);
+                        ui.weak(entry.template.size.label());
+
+                        if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                            requests.send(LibraryRequest::Remove(entry.id));
+                        }
+                    });
+                }
+            });
+    });
+
+    let Some(dragged) = library_window.dragging else {
+        return;
+    };
+    let Some(size) = library
+        .entries
+        .iter()
+        .find(|entry| entry.id == dragged)
+        .map(|entry| entry.template.size)
+    else {
+        library_window.dragging = None;
+        return;
+    };
+
+    egui_context
+        .get_mut()
+        .set_cursor_icon(egui::CursorIcon::Grabbing);
+
+    if !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+    library_window.dragging = None;
+
+    // Dropping it back onto a window cancels
+    if **over_ui {
+        return;
+    }
+
+    let free = key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    requests.send(LibraryRequest::Place {
+        entry: dragged,
+        position: placement_position(&cursor_pos, &grids, size, free),
+    });
+}
@@ -1,11 +1,71 @@
 use crate::prelude::*;
+use bevy::utils::HashMap;
+use bevy_egui::EguiUserTextures;
 
 mod chat;
+mod conditions;
 mod connection;
+mod grid;
+mod initiative;
+mod library;
+mod recovery;
+mod scenes;
+mod session;
+mod token_menu;
+mod tokens;
+mod toolbar;
 
 pub struct WindowPlugin;
 impl Plugin for WindowPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((chat::ChatWindowPlugin, connection::ConnectionWindowPlugin));
+        app.add_plugins((
+            chat::ChatWindowPlugin,
+            conditions::ConditionsWindowPlugin,
+            connection::ConnectionWindowPlugin,
+            grid::GridWindowPlugin,
+            initiative::InitiativeWindowPlugin,
+            library::LibraryWindowPlugin,
+            recovery::RecoveryWindowPlugin,
+            scenes::ScenesWindowPlugin,
+            session::SessionWindowPlugin,
+            token_menu::TokenMenuWindowPlugin,
+            tokens::TokensWindowPlugin,
+            toolbar::ToolbarWindowPlugin,
+        ));
+    }
+}
+
+/// Egui textures of the images a window shows, registered once and freed when they go away
+#[derive(Debug, Default, Clone)]
+pub struct Thumbnails(HashMap<Uuid, (Handle<Image>, egui::TextureId)>);
+
+impl Thumbnails {
+    /// Registers images that are new or got another handle, and frees the ones no longer shown
+    pub fn update(
+        &mut self,
+        user_textures: &mut EguiUserTextures,
+        images: impl IntoIterator<Item = (Uuid, Handle<Image>)>,
+    ) {
+        let mut old = std::mem::take(&mut self.0);
+
+        for (id, handle) in images {
+            let texture = match old.remove(&id) {
+                Some((old_handle, texture)) if old_handle == handle => texture,
+                Some((old_handle, _)) => {
+                    user_textures.remove_image(&old_handle);
+                    user_textures.add_image(handle.clone())
+                }
+                None => user_textures.add_image(handle.clone()),
+            };
+            self.0.insert(id, (handle, texture));
+        }
+
+        for (handle, _) in old.into_values() {
+            user_textures.remove_image(&handle);
+        }
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<egui::TextureId> {
+        self.0.get(&id).map(|(_, texture)| *texture)
     }
 }
@@ -0,0 +1,75 @@
+use crate::{
+    networking::autosave::{remove_lock, PendingRecovery, RecoveryOffer},
+    prelude::*,
+};
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+use server::ServerCommands;
+
+pub struct RecoveryWindowPlugin;
+impl Plugin for RecoveryWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            display_window.run_if(resource_exists::<RecoveryOffer>),
+        );
+
+        // Create window
+        app.world
+            .spawn((Name::new("Recovery Window"), RecoveryWindow));
+    }
+}
+
+/// Offers to restore the autosave of a session that didn't shut down cleanly
+#[derive(Component, Debug, Default, Clone)]
+pub struct RecoveryWindow;
+
+fn display_window(
+    mut commands: Commands,
+    mut egui_context: Query<&mut EguiContext>,
+    recovery_window: Query<Entity, With<RecoveryWindow>>,
+    offer: Res<RecoveryOffer>,
+    server_state: Res<State<server::NetworkingState>>,
+    client_state: Res<State<client::NetworkingState>>,
+) {
+    let entity = recovery_window.single();
+    let mut egui_context = egui_context.single_mut();
+
+    let is_host = matches!(server_state.get(), server::NetworkingState::Started);
+    let can_host = is_host || matches!(client_state.get(), client::NetworkingState::Disconnected);
+
+    let window = egui::Window::new("Recover session")
+        .id(egui::Id::new(entity))
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false);
+
+    window.show(egui_context.get_mut(), |ui| {
+        ui.label("The last hosted session didn't shut down cleanly.");
+        ui.label(format!("Latest autosave: {}", offer.0.display()));
+
+        ui.horizontal(|ui| {
+            let restore = ui
+                .add_enabled(can_host, egui::Button::new("Restore"))
+                .on_hover_text("Host the session as it was autosaved")
+                .on_disabled_hover_text("Disconnect first to host the session");
+
+            if restore.clicked() {
+                commands.insert_resource(PendingRecovery(offer.0.clone()));
+                commands.remove_resource::<RecoveryOffer>();
+
+                if !is_host {
+                    commands.start_server();
+                }
+            }
+
+            if ui.button("Discard").clicked() {
+                commands.remove_resource::<RecoveryOffer>();
+
+                if !is_host {
+                    remove_lock();
+                }
+            }
+        });
+    });
+}
@@ -0,0 +1,181 @@
+use crate::{
+    networking::{
+        client::{local_player_is_gm, ClientId},
+        scenes::client_scene,
+    },
+    prelude::*,
+};
+use bevy_egui::EguiContext;
+use lightyear::prelude::client::*;
+
+pub struct ScenesWindowPlugin;
+impl Plugin for ScenesWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_window.run_if(local_player_is_gm));
+
+        // Create window
+        app.world
+            .spawn((Name::new("Scenes Window"), ScenesWindow::default()));
+    }
+}
+
+/// GM's list of scenes, and which scene each player is on
+#[derive(Component, Debug, Default, Clone)]
+pub struct ScenesWindow {
+    new_scene: String,
+    /// Scene being renamed, with the name typed so far
+    renaming: Option<(Entity, String)>,
+}
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    mut scenes_window: Query<(Entity, &mut ScenesWindow)>,
+    scenes: Query<(Entity, &TableScene)>,
+    player_data: Res<PlayerData>,
+    clients: Res<ConnectedClients>,
+    client_id: Res<ClientId>,
+    mut connection: ResMut<ConnectionManager>,
+) {
+    let (entity, mut scenes_window) = scenes_window.single_mut();
+    let scenes_window = scenes_window.as_mut();
+    let mut egui_context = egui_context.single_mut();
+
+    let mut sorted_scenes = scenes.iter().collect::<Vec<_>>();
+    sorted_scenes.sort_by_key(|(entity, _)| *entity);
+
+    let viewing = client_scene(scenes.iter(), client_id.0);
+    let mut messages = Vec::new();
+
+    let window = egui::Window::new("Scenes")
+        .id(egui::Id::new(entity))
+        .default_open(false)
+        .collapsible(true);
+
+    window.show(egui_context.get_mut(), |ui| {
+        egui::Grid::new("Scene list").show(ui, |ui| {
+            for (scene_entity, scene) in sorted_scenes.iter().copied() {
+                match &mut scenes_window.renaming {
+                    Some((renamed, name)) if *renamed == scene_entity => {
+                        let response = ui.add(
+                            egui::TextEdit::singleline(name)
+                                .char_limit(32)
+                                .desired_width(120.0),
+                        );
+
+                        if response.lost_focus() {
+                            if ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                                messages.push(SceneMessage::Rename(scene_entity, name.clone()));
+                            }
+                            scenes_window.renaming = None;
+                        } else {
+                            response.request_focus();
+                        }
+                    }
+                    _ => {
+                        let label = ui
+                            .add(egui::Label::new(&scene.name).sense(egui::Sense::click()))
+                            .on_hover_text("Double click to rename");
+
+                        if label.double_clicked() {
+                            scenes_window.renaming = Some((scene_entity, scene.name.clone()));
+                        }
+                    }
+                }
+
+                if scene.active {
+                    ui.label("Active");
+                } else if ui
+                    .button("Activate")
+                    .on_hover_text("Move every player to this scene")
+                    .clicked()
+                {
+                    messages.push(SceneMessage::Activate(scene_entity));
+                }
+
+                let is_viewing = viewing == Some(scene_entity);
+                let view = ui
+                    .add_enabled(!is_viewing, egui::Button::new("View"))
+                    .on_hover_text("Look at this scene without moving players");
+                if view.clicked() {
+                    messages.push(SceneMessage::Park(client_id.0, Some(scene_entity)));
+                }
+
+                if ui.button("Duplicate").clicked() {
+                    messages.push(SceneMessage::Duplicate(scene_entity));
+                }
+                ui.end_row();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut scenes_window.new_scene)
+                    .char_limit(32)
+                    .hint_text("New scene")
+                    .desired_width(120.0),
+            );
+
+            if ui.button("Create").clicked() {
+                messages.push(SceneMessage::Create(scenes_window.new_scene.clone()));
+                scenes_window.new_scene.clear();
+            }
+        });
+
+        let mut players = clients
+            .iter()
+            .copied()
+            .filter(|client| *client != client_id.0)
+            .collect::<Vec<_>>();
+
+        if players.is_empty() {
+            return;
+        }
+        players.sort_unstable();
+
+        ui.separator();
+        ui.label("Players");
+
+        egui::Grid::new("Player scenes").show(ui, |ui| {
+            for player in players {
+                let name = player_data
+                    .get(&player)
+                    .map(|player| player.name.as_str())
+                    .unwrap_or("Player");
+                ui.label(name);
+
+                let parked = sorted_scenes
+                    .iter()
+                    .find(|(_, scene)| scene.parked.contains(&player))
+                    .map(|(entity, _)| *entity);
+                let mut choice = parked;
+
+                let scene_name = |choice: Option<Entity>| {
+                    choice
+                        .and_then(|choice| scenes.get(choice).ok())
+                        .map(|(_, scene)| scene.name.clone())
+                        .unwrap_or_else(|| String::from("Active scene"))
+                };
+
+                egui::ComboBox::from_id_source(("Player scene", player))
+                    .selected_text(scene_name(choice))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut choice, None, "Active scene");
+                        for (scene_entity, scene) in sorted_scenes.iter() {
+                            if !scene.active {
+                                ui.selectable_value(&mut choice, Some(*scene_entity), &scene.name);
+                            }
+                        }
+                    });
+
+                if choice != parked {
+                    messages.push(SceneMessage::Park(player, choice));
+                }
+                ui.end_row();
+            }
+        });
+    });
+
+    for message in messages {
+        _ = connection.send_message::<UnorderedReliable, _>(&message);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{
+    networking::session::{saved_sessions, SessionRequest},
+    prelude::*,
+};
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+
+pub struct SessionWindowPlugin;
+impl Plugin for SessionWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            display_window.run_if(in_state(server::NetworkingState::Started)),
+        );
+
+        // Create window
+        app.world
+            .spawn((Name::new("Session Window"), SessionWindow::default()));
+    }
+}
+
+/// Host's window for saving the session and loading earlier ones
+#[derive(Component, Debug, Default, Clone)]
+pub struct SessionWindow {
+    name: String,
+    /// Saves found on disk, read again when asked for
+    saves: Option<Vec<String>>,
+}
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    mut session_window: Query<(Entity, &mut SessionWindow)>,
+    mut requests: EventWriter<SessionRequest>,
+) {
+    let (entity, mut session_window) = session_window.single_mut();
+    let session_window = session_window.as_mut();
+    let mut egui_context = egui_context.single_mut();
+
+    let window = egui::Window::new("Session")
+        .id(egui::Id::new(entity))
+        .default_open(false)
+        .collapsible(true);
+
+    window.show(egui_context.get_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut session_window.name)
+                    .hint_text("Session name")
+                    .desired_width(140.0),
+            );
+
+            let has_name = !session_window.name.trim().is_empty();
+
+            if ui
+                .add_enabled(has_name, egui::Button::new("Save"))
+                .clicked()
+            {
+                requests.send(SessionRequest::Save(session_window.name.clone()));
+                session_window.saves = None;
+            }
+
+            if ui
+                .add_enabled(has_name, egui::Button::new("Load"))
+                .clicked()
+            {
+                requests.send(SessionRequest::Load(session_window.name.clone()));
+            }
+        });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Saved sessions");
+            if ui.small_button("⟳").on_hover_text("Refresh").clicked() {
+                session_window.saves = None;
+            }
+        });
+
+        let saves = session_window.saves.get_or_insert_with(saved_sessions);
+
+        if saves.is_empty() {
+            ui.weak("Nothing saved yet");
+        }
+
+        for save in saves.iter() {
+            if ui
+                .selectable_label(session_window.name == *save, save)
+                .clicked()
+            {
+                session_window.name = save.clone();
+            }
+        }
+    });
+}
@@ -0,0 +1,541 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::networking::library::LibraryRequest;
+use crate::{
+    networking::{
+        asset_sharing::SharedAssets,
+        client::{local_role, ClientId},
+        tokens::{TOKEN_LAYERS, TOKEN_SIZES},
+    },
+    prelude::*,
+    tabletop::{shared_image_handle, TokenClicked},
+};
+use bevy::utils::{HashMap, HashSet};
+use bevy_egui::{EguiContext, EguiUserTextures};
+use lightyear::prelude::*;
+
+pub struct TokenMenuWindowPlugin;
+impl Plugin for TokenMenuWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (open_token_menu, display_window).chain());
+
+        // Create window
+        app.world
+            .spawn((Name::new("Token Menu Window"), TokenMenuWindow::default()));
+    }
+}
+
+/// Menu opened by right clicking a token
+#[derive(Component, Debug, Clone)]
+pub struct TokenMenuWindow {
+    pub target: Option<Entity>,
+    pub position: Vec2,
+    /// Click that opened the menu shouldn't close it right away
+    just_opened: bool,
+    name: String,
+    /// Size and layer being dragged, only sent once the drag is over
+    size: TokenSize,
+    layer: f32,
+    custom_condition: Condition,
+    new_bar_label: String,
+    bar_drafts: HashMap<Entity, BarDraft>,
+}
+
+/// Value and max of a bar being dragged or typed into
+#[derive(Debug, Clone, Copy)]
+struct BarDraft {
+    /// Value the edit started from, the difference to it is sent as an adjustment
+    from: i32,
+    value: i32,
+    max: i32,
+}
+
+impl Default for TokenMenuWindow {
+    fn default() -> Self {
+        Self {
+            target: None,
+            position: Vec2::ZERO,
+            just_opened: false,
+            name: String::new(),
+            size: TokenSize::default(),
+            layer: 0.0,
+            custom_condition: Condition::new("", "", [200, 200, 200]),
+            new_bar_label: String::new(),
+            bar_drafts: HashMap::default(),
+        }
+    }
+}
+
+fn open_token_menu(
+    mut clicks: EventReader<TokenClicked>,
+    mut token_menu: Query<&mut TokenMenuWindow>,
+) {
+    let mut token_menu = token_menu.single_mut();
+
+    for click in clicks
+        .read()
+        .filter(|click| click.button == PointerButton::Secondary)
+    {
+        token_menu.target = Some(click.entity);
+        token_menu.position = click.position;
+        token_menu.just_opened = true;
+    }
+}
+
+/// Everything about a token the menu shows
+type TokenProperties = (
+    &'static Token,
+    Option<&'static TokenName>,
+    Option<&'static SharedAsset<Image>>,
+    Option<&'static TokenSize>,
+    Has<TokenLocked>,
+    Option<&'static TokenConditions>,
+    Option<&'static TokenOwners>,
+);
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    mut token_menu: Query<(Entity, &mut TokenMenuWindow)>,
+    tokens: Query<TokenProperties, With<Token>>,
+    images: Query<&SharedAsset<Image>, With<Token>>,
+    bars: Query<(Entity, &ResourceBar)>,
+    catalogue: Res<ConditionCatalogue>,
+    client_id: Res<ClientId>,
+    roles: Res<PlayerRoles>,
+    player_data: Res<PlayerData>,
+    clients: Res<ConnectedClients>,
+    shared_images: Res<SharedAssets<Image>>,
+    mut user_textures: ResMut<EguiUserTextures>,
+    server_state: Res<State<server::NetworkingState>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut connection: ResMut<client::ConnectionManager>,
+    #[cfg(not(target_arch = "wasm32"))] mut library_requests: EventWriter<LibraryRequest>,
+) {
+    let (entity, mut token_menu) = token_menu.single_mut();
+    let token_menu = token_menu.as_mut();
+
+    let Some(target) = token_menu.target else {
+        return;
+    };
+
+    // Token might have been deleted or hidden while the menu was open
+    let Ok((token, name, image, size, locked, conditions, owners)) = tokens.get(target) else {
+        token_menu.target = None;
+        return;
+    };
+
+    if token_menu.just_opened {
+        token_menu.name = name.map(|name| name.0.clone()).unwrap_or_default();
+        token_menu.size = size.copied().unwrap_or_default();
+        token_menu.layer = token.layer;
+        token_menu.bar_drafts.clear();
+    }
+
+    let role = local_role(&client_id, &roles, &server_state);
+    let can_edit = role.can_control(client_id.0, Permission::EditTokens, owners);
+    let is_gm = role == Role::Gm;
+
+    // Any image already on the table can be given to the token
+    let mut table_images = images.iter().map(|image| image.id).collect::<Vec<_>>();
+    table_images.sort_unstable();
+    table_images.dedup();
+    let table_images = table_images
+        .into_iter()
+        .map(|id| {
+            let handle = shared_image_handle(&shared_images, id);
+            (id, user_textures.add_image(handle))
+        })
+        .collect::<Vec<_>>();
+
+    let mut edits = Vec::new();
+    let mut token_messages = Vec::new();
+
+    let current = conditions.cloned().unwrap_or_default();
+    let mut conditions = current.clone();
+
+    let mut token_bars = bars
+        .iter()
+        .filter(|(_, bar)| bar.token == target)
+        .collect::<Vec<_>>();
+    token_bars.sort_by_key(|(entity, _)| *entity);
+    let mut bar_messages = Vec::new();
+
+    let mut egui_context = egui_context.single_mut();
+    let area = egui::Area::new(egui::Id::new(entity))
+        .fixed_pos(egui::pos2(token_menu.position.x, token_menu.position.y))
+        .order(egui::Order::Foreground);
+
+    let response = area.show(egui_context.get_mut(), |ui| {
+        egui::Frame::popup(ui.style()).show(ui, |ui| {
+            ui.set_max_width(220.0);
+            ui.add_enabled_ui(can_edit, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut token_menu.name)
+                        .hint_text("Name")
+                        .char_limit(32),
+                );
+                let current_name = name.map(|name| name.0.as_str()).unwrap_or_default();
+                if response.lost_focus() && token_menu.name.trim() != current_name {
+                    edits.push(TokenEdit::Rename(token_menu.name.clone()));
+                }
+
+                ui.horizontal_wrapped(|ui| {
+                    for (id, texture) in table_images.iter() {
+                        let selected = image.is_some_and(|image| image.id == *id);
+                        let thumbnail = egui::load::SizedTexture::new(*texture, [32.0, 32.0]);
+
+                        if ui
+                            .add(egui::ImageButton::new(thumbnail).selected(selected))
+                            .clicked()
+                            && !selected
+                        {
+                            edits.push(TokenEdit::SetImage(*id));
+                        }
+                    }
+                });
+
+                if is_gm {
+                    ui.separator();
+                    gm_ui(
+                        ui,
+                        target,
+                        token,
+                        size.copied().unwrap_or_default(),
+                        &mut token_menu.size,
+                        &mut token_menu.layer,
+                        locked,
+                        owners,
+                        &clients,
+                        &player_data,
+                        client_id.0,
+                        &mut edits,
+                        &mut token_messages,
+                    );
+
+                    // Library is kept on the host's disk
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        let hosting =
+                            matches!(server_state.get(), server::NetworkingState::Started);
+                        if hosting && ui.button("Add to library").clicked() {
+                            library_requests.send(LibraryRequest::Add(target));
+                        }
+                    }
+                }
+
+                ui.separator();
+                ui.label("Conditions");
+
+                // Catalogue first, then custom conditions this token already has
+                let extra = conditions
+                    .iter()
+                    .filter(|condition| !catalogue.contains(condition))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                for condition in catalogue.iter().chain(extra.iter()) {
+                    let mut active = conditions.contains(condition);
+                    let text = format!("{} {}", condition.icon, condition.label);
+
+                    if ui.checkbox(&mut active, text).changed() {
+                        if active {
+                            conditions.push(condition.clone());
+                        } else {
+                            conditions.retain(|other| other != condition);
+                        }
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let custom = &mut token_menu.custom_condition;
+                    ui.color_edit_button_srgb(&mut custom.color);
+                    ui.add(
+                        egui::TextEdit::singleline(&mut custom.icon)
+                            .hint_text("Icon")
+                            .char_limit(2)
+                            .desired_width(24.0),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut custom.label)
+                            .hint_text("Custom condition")
+                            .desired_width(100.0),
+                    );
+
+                    let valid = !custom.label.trim().is_empty() && !conditions.contains(custom);
+                    if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+                        conditions.push(custom.clone());
+                        custom.label.clear();
+                        custom.icon.clear();
+                    }
+                });
+
+                ui.separator();
+                bars_ui(
+                    ui,
+                    target,
+                    &token_bars,
+                    role == Role::Gm,
+                    &mut token_menu.new_bar_label,
+                    &mut token_menu.bar_drafts,
+                    &mut bar_messages,
+                );
+            });
+        });
+    });
+
+    if conditions != current {
+        edits.push(TokenEdit::SetConditions(conditions.0));
+    }
+
+    for edit in edits {
+        let message = EditTokenMessage {
+            entity: target,
+            edit,
+        };
+        _ = connection.send_message::<UnorderedReliable, _>(&message);
+    }
+
+    for message in bar_messages {
+        _ = connection.send_message::<UnorderedReliable, _>(&message);
+    }
+
+    let deleted = token_messages
+        .iter()
+        .any(|message| matches!(message, TokenMessage::Delete(_)));
+    for message in token_messages {
+        _ = connection.send_message::<UnorderedReliable, _>(&message);
+    }
+
+    let close = deleted
+        || key_input.just_pressed(KeyCode::Escape)
+        || (!token_menu.just_opened && response.response.clicked_elsewhere());
+    token_menu.just_opened = false;
+
+    if close {
+        token_menu.target = None;
+    }
+}
+
+/// Dragging a value would send an edit every frame, so it's sent once the drag or typing is done
+fn edit_done(response: &egui::Response) -> bool {
+    response.drag_released() || response.lost_focus()
+}
+
+/// Size, layer, lock and owners of the token, which only the GM may change
+fn gm_ui(
+    ui: &mut egui::Ui,
+    target: Entity,
+    token: &Token,
+    size: TokenSize,
+    new_size: &mut TokenSize,
+    layer: &mut f32,
+    locked: bool,
+    owners: Option<&TokenOwners>,
+    clients: &ConnectedClients,
+    player_data: &PlayerData,
+    local_id: u64,
+    edits: &mut Vec<TokenEdit>,
+    token_messages: &mut Vec<TokenMessage>,
+) {
+    egui::Grid::new("Token properties").show(ui, |ui| {
+        ui.label("Size");
+        egui::ComboBox::from_id_source("Token size")
+            .selected_text(size.label())
+            .show_ui(ui, |ui| {
+                for category in SizeCategory::ALL {
+                    let selected = size.category() == Some(category);
+                    let label = format!("{category:?}");
+                    if ui.selectable_label(selected, label).clicked() && !selected {
+                        *new_size = category.into();
+                        edits.push(TokenEdit::SetSize(*new_size));
+                    }
+                }
+            });
+        ui.end_row();
+
+        ui.label("");
+        ui.horizontal(|ui| {
+            let width = ui.add(
+                egui::DragValue::new(&mut new_size.width)
+                    .speed(0.05)
+                    .clamp_range(TOKEN_SIZES),
+            );
+            ui.label("×");
+            let height = ui.add(
+                egui::DragValue::new(&mut new_size.height)
+                    .speed(0.05)
+                    .clamp_range(TOKEN_SIZES),
+            );
+
+            if (edit_done(&width) || edit_done(&height)) && *new_size != size {
+                edits.push(TokenEdit::SetSize(*new_size));
+            }
+        });
+        ui.end_row();
+
+        ui.label("Layer");
+        let response = ui.add(
+            egui::DragValue::new(layer)
+                .speed(0.1)
+                .clamp_range(TOKEN_LAYERS),
+        );
+        ui.end_row();
+
+        if edit_done(&response) && *layer != token.layer {
+            edits.push(TokenEdit::SetLayer(*layer));
+        }
+
+        let mut new_locked = locked;
+        ui.label("Locked");
+        ui.checkbox(&mut new_locked, "")
+            .on_hover_text("Locked tokens can't be dragged");
+        ui.end_row();
+
+        if new_locked != locked {
+            edits.push(TokenEdit::SetLocked(new_locked));
+        }
+    });
+
+    let current_owners = owners.map(|owners| owners.0.clone()).unwrap_or_default();
+    let mut players = clients
+        .iter()
+        .chain(current_owners.iter())
+        .copied()
+        .filter(|client| *client != local_id)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    players.sort_unstable();
+
+    if !players.is_empty() {
+        ui.label("Owners");
+
+        let mut new_owners = current_owners.clone();
+        for player in players {
+            let name = player_data
+                .get(&player)
+                .map(|player| player.name.clone())
+                .unwrap_or_else(|| player.to_string());
+            let mut owns = new_owners.contains(&player);
+
+            if ui.checkbox(&mut owns, name).changed() {
+                if owns {
+                    new_owners.insert(player);
+                } else {
+                    new_owners.remove(&player);
+                }
+            }
+        }
+
+        if new_owners != current_owners {
+            edits.push(TokenEdit::SetOwners(new_owners));
+        }
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("Duplicate").clicked() {
+            token_messages.push(TokenMessage::Duplicate(target));
+        }
+        if ui.button("Delete").clicked() {
+            token_messages.push(TokenMessage::Delete(target));
+        }
+    });
+}
+
+/// Values of the token's bars with quick +/- buttons. Only the GM may hide bars from players
+fn bars_ui(
+    ui: &mut egui::Ui,
+    token: Entity,
+    bars: &[(Entity, &ResourceBar)],
+    is_gm: bool,
+    new_bar_label: &mut String,
+    drafts: &mut HashMap<Entity, BarDraft>,
+    messages: &mut Vec<BarMessage>,
+) {
+    ui.label("Bars");
+
+    egui::Grid::new("Token bars").show(ui, |ui| {
+        for (entity, bar) in bars {
+            let mut edited = (*bar).clone();
+            let draft = drafts.entry(*entity).or_insert(BarDraft {
+                from: bar.value,
+                value: bar.value,
+                max: bar.max,
+            });
+
+            ui.label(&bar.label);
+            if ui.small_button("-").clicked() {
+                messages.push(BarMessage::Adjust(*entity, -1));
+            }
+            let value = ui.add(egui::DragValue::new(&mut draft.value));
+            if ui.small_button("+").clicked() {
+                messages.push(BarMessage::Adjust(*entity, 1));
+            }
+            ui.label("/");
+            let max = ui.add(egui::DragValue::new(&mut draft.max).clamp_range(0..=i32::MAX));
+            ui.color_edit_button_srgb(&mut edited.color);
+
+            // Sent as a difference, so +/- edits of other players in the meantime aren't lost
+            if edit_done(&value) && draft.value != draft.from {
+                messages.push(BarMessage::Adjust(
+                    *entity,
+                    draft.value.saturating_sub(draft.from),
+                ));
+            }
+            if edit_done(&max) {
+                edited.max = draft.max;
+            }
+
+            let in_use = |response: &egui::Response| response.dragged() || response.has_focus();
+            if !in_use(&value) && !in_use(&max) {
+                drafts.remove(entity);
+            }
+
+            egui::ComboBox::from_id_source(("Bar visibility", *entity))
+                .selected_text(edited.visibility.name())
+                .width(70.0)
+                .show_ui(ui, |ui| {
+                    for visibility in BarVisibility::ALL {
+                        if visibility != BarVisibility::Gm || is_gm {
+                            ui.selectable_value(
+                                &mut edited.visibility,
+                                visibility,
+                                visibility.name(),
+                            );
+                        }
+                    }
+                });
+
+            if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                messages.push(BarMessage::Remove(*entity));
+            }
+            ui.end_row();
+
+            if edited != **bar {
+                messages.push(BarMessage::Update(*entity, edited));
+            }
+        }
+    });
+
+    ui.horizontal(|ui| {
+        if !bars.iter().any(|(_, bar)| bar.label == "HP") && ui.button("Add HP").clicked() {
+            messages.push(BarMessage::Add(ResourceBar::hit_points(token)));
+        }
+
+        ui.add(
+            egui::TextEdit::singleline(new_bar_label)
+                .hint_text("Custom bar")
+                .desired_width(80.0),
+        );
+
+        let valid = !new_bar_label.trim().is_empty();
+        if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+            messages.push(BarMessage::Add(ResourceBar {
+                label: new_bar_label.trim().to_owned(),
+                color: [60, 120, 220],
+                ..ResourceBar::hit_points(token)
+            }));
+            new_bar_label.clear();
+        }
+    });
+}
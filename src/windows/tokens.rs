@@ -0,0 +1,89 @@
+use crate::{
+    networking::{asset_sharing::SharedAssets, client::local_player_is_gm},
+    prelude::*,
+    tabletop::{shared_image_handle, spawning::TokenPlacement},
+    windows::Thumbnails,
+};
+use bevy_egui::{EguiContext, EguiUserTextures};
+
+pub struct TokensWindowPlugin;
+impl Plugin for TokensWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_window.run_if(local_player_is_gm));
+
+        // Create window
+        app.world
+            .spawn((Name::new("Tokens Window"), TokensWindow::default()));
+    }
+}
+
+/// GM's window for putting new tokens on the table
+#[derive(Component, Debug, Default, Clone)]
+pub struct TokensWindow {
+    thumbnails: Thumbnails,
+}
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    mut tokens_window: Query<(Entity, &mut TokensWindow)>,
+    images: Query<&SharedAsset<Image>>,
+    shared_images: Res<SharedAssets<Image>>,
+    mut user_textures: ResMut<EguiUserTextures>,
+    mut placement: ResMut<TokenPlacement>,
+) {
+    let (entity, mut tokens_window) = tokens_window.single_mut();
+    let mut egui_context = egui_context.single_mut();
+
+    // Images shared so far, whether or not a token still shows them
+    let mut known_images = shared_images
+        .id_to_handle
+        .keys()
+        .copied()
+        .chain(images.iter().map(|image| image.id))
+        .collect::<Vec<_>>();
+    known_images.sort_unstable();
+    known_images.dedup();
+
+    tokens_window.thumbnails.update(
+        &mut user_textures,
+        known_images
+            .iter()
+            .map(|id| (*id, shared_image_handle(&shared_images, *id))),
+    );
+
+    let window = egui::Window::new("Tokens")
+        .id(egui::Id::new(entity))
+        .default_open(false)
+        .collapsible(true);
+
+    window.show(egui_context.get_mut(), |ui| {
+        if known_images.is_empty() {
+            ui.weak("No images shared yet");
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            for id in known_images {
+                let Some(texture) = tokens_window.thumbnails.get(id) else {
+                    continue;
+                };
+                let thumbnail = egui::load::SizedTexture::new(texture, [48.0, 48.0]);
+                let selected = placement.image == Some(id);
+
+                if ui
+                    .add(egui::ImageButton::new(thumbnail).selected(selected))
+                    .clicked()
+                {
+                    placement.image = if selected { None } else { Some(id) };
+                }
+            }
+        });
+
+        ui.separator();
+        if placement.image.is_some() {
+            ui.label("Click to place, hold Shift to place off the grid");
+            ui.weak("Right click or Escape to stop");
+        } else {
+            ui.weak("Pick an image to place tokens with it");
+        }
+    });
+}
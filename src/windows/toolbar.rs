@@ -0,0 +1,164 @@
+use crate::{
+    networking::client::{local_role, ClientId},
+    prelude::*,
+    tabletop::{
+        drawing::{DrawMode, DrawTool},
+        fog::{FogMode, FogTool},
+        scenes::OffScene,
+        templates::TemplateTool,
+        tools::Tool,
+        walls::WallTool,
+    },
+};
+use bevy_egui::EguiContext;
+use lightyear::prelude::*;
+
+pub struct ToolbarWindowPlugin;
+impl Plugin for ToolbarWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_window);
+
+        // Create window
+        app.world
+            .spawn((Name::new("Toolbar Window"), ToolbarWindow));
+    }
+}
+
+#[derive(Component, Debug, Default, Clone)]
+pub struct ToolbarWindow;
+
+fn display_window(
+    mut egui_context: Query<&mut EguiContext>,
+    toolbar_window: Query<Entity, With<ToolbarWindow>>,
+    mut tool: ResMut<Tool>,
+    mut template_tool: ResMut<TemplateTool>,
+    mut draw_tool: ResMut<DrawTool>,
+    mut fog_tool: ResMut<FogTool>,
+    mut wall_tool: ResMut<WallTool>,
+    fogs: Query<&FogOfWar, Without<OffScene>>,
+    client_id: Res<ClientId>,
+    roles: Res<PlayerRoles>,
+    server_state: Res<State<server::NetworkingState>>,
+    mut connection: ResMut<client::ConnectionManager>,
+) {
+    let is_gm = local_role(&client_id, &roles, &server_state) == Role::Gm;
+    let entity = toolbar_window.single();
+    let mut egui_context = egui_context.single_mut();
+    let mut selected = *tool;
+
+    let window = egui::Window::new("Tools")
+        .id(egui::Id::new(entity))
+        .resizable(false)
+        .collapsible(true);
+
+    window.show(egui_context.get_mut(), |ui| {
+        ui.horizontal(|ui| {
+            for option in Tool::ALL
+                .into_iter()
+                .filter(|tool| is_gm || !tool.gm_only())
+            {
+                ui.selectable_value(&mut selected, option, option.name())
+                    .on_hover_text(format!("{:?}", option.shortcut()));
+            }
+        });
+
+        if selected == Tool::Template {
+            ui.separator();
+            ui.horizontal(|ui| {
+                for shape in TemplateShape::ALL {
+                    if ui
+                        .selectable_label(template_tool.shape == shape, shape.name())
+                        .clicked()
+                    {
+                        template_tool.shape = shape;
+                    }
+                }
+            });
+        }
+
+        if selected == Tool::Draw {
+            ui.separator();
+            ui.horizontal(|ui| {
+                for mode in DrawMode::ALL {
+                    ui.selectable_value(&mut draw_tool.mode, mode, mode.name());
+                }
+            });
+
+            match draw_tool.mode {
+                DrawMode::Pen => {
+                    ui.add(egui::Slider::new(&mut draw_tool.pen_width, 0.01..=0.5).text("Width"));
+                }
+                DrawMode::Highlighter => {
+                    ui.add(
+                        egui::Slider::new(&mut draw_tool.highlighter_width, 0.1..=2.0)
+                            .text("Width"),
+                    );
+                }
+                DrawMode::Eraser => (),
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Clear my drawings").clicked() {
+                    _ = connection.send_message::<UnorderedReliable, _>(&DrawMessage::ClearMine);
+                }
+                if is_gm && ui.button("Clear all").clicked() {
+                    _ = connection.send_message::<UnorderedReliable, _>(&DrawMessage::ClearAll);
+                }
+            });
+        }
+
+        if selected == Tool::Walls {
+            ui.separator();
+            ui.horizontal(|ui| {
+                for kind in WallKind::ALL {
+                    ui.selectable_value(&mut wall_tool.kind, kind, kind.name());
+                }
+            });
+            ui.label("Right click removes a wall, L locks a door");
+        }
+
+        if selected == Tool::Fog {
+            ui.separator();
+            ui.horizontal(|ui| {
+                for mode in FogMode::ALL {
+                    ui.selectable_value(&mut fog_tool.mode, mode, mode.name());
+                }
+            });
+
+            if matches!(fog_tool.mode, FogMode::RevealBrush | FogMode::HideBrush) {
+                ui.add(
+                    egui::Slider::new(&mut fog_tool.brush_radius, 0.25..=10.0).text("Brush size"),
+                );
+            }
+
+            if let Some(fog) = fogs.iter().next() {
+                ui.horizontal(|ui| {
+                    let mut enabled = fog.enabled;
+                    if ui.checkbox(&mut enabled, "Enabled").changed() {
+                        _ = connection
+                            .send_message::<UnorderedReliable, _>(&FogMessage::SetEnabled(enabled));
+                    }
+                    let mut vision = fog.vision;
+                    if ui
+                        .checkbox(&mut vision, "Token vision")
+                        .on_hover_text("Players only see what their tokens can see")
+                        .changed()
+                    {
+                        _ = connection
+                            .send_message::<UnorderedReliable, _>(&FogMessage::SetVision(vision));
+                    }
+                    if ui.button("Reveal all").clicked() {
+                        _ = connection.send_message::<UnorderedReliable, _>(&FogMessage::RevealAll);
+                    }
+                    if ui.button("Hide all").clicked() {
+                        _ = connection.send_message::<UnorderedReliable, _>(&FogMessage::HideAll);
+                    }
+                });
+            }
+        }
+    });
+
+    if selected != *tool {
+        *tool = selected;
+    }
+}